@@ -0,0 +1,84 @@
+use crate::instance::{get_game_config_dir, Instance};
+use core::fmt;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Where the game keeps its preferences, saves and plugins:
+/// the regular config dir shared by most instances, or the one of an instance with its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ConfigLocation {
+    #[default]
+    Shared,
+    Instance(String),
+}
+
+impl ConfigLocation {
+    /// All locations that currently exist, in display order.
+    pub fn all(instances: &BTreeMap<String, Instance>) -> Vec<Self> {
+        std::iter::once(Self::Shared)
+            .chain(
+                instances
+                    .values()
+                    .filter(|i| i.isolated_config)
+                    .map(|i| Self::Instance(i.name.clone())),
+            )
+            .collect()
+    }
+
+    pub fn config_dir(&self, instances: &BTreeMap<String, Instance>) -> Option<PathBuf> {
+        match self {
+            Self::Shared => get_game_config_dir(),
+            Self::Instance(name) => instances.get(name)?.config_dir(),
+        }
+    }
+
+    pub fn saves_dir(&self, instances: &BTreeMap<String, Instance>) -> Option<PathBuf> {
+        Some(self.config_dir(instances)?.join("saves"))
+    }
+
+    pub fn plugins_dir(&self, instances: &BTreeMap<String, Instance>) -> Option<PathBuf> {
+        Some(self.config_dir(instances)?.join("plugins"))
+    }
+
+    /// Follows a renamed instance, returning whether this location belongs to it.
+    pub fn rename_instance(&mut self, old_name: &str, new_name: &str) -> bool {
+        match self {
+            Self::Instance(name) if name == old_name => {
+                *name = new_name.to_string();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The instances that use this location.
+    pub fn instances<'a>(
+        &self,
+        instances: &'a BTreeMap<String, Instance>,
+    ) -> impl Iterator<Item = &'a Instance> + 'a {
+        let location = self.clone();
+        instances.values().filter(move |i| match &location {
+            Self::Shared => !i.isolated_config,
+            Self::Instance(name) => &i.name == name,
+        })
+    }
+
+    /// The instance that will most likely run with this location next.
+    pub fn instance<'a>(&self, instances: &'a BTreeMap<String, Instance>) -> Option<&'a Instance> {
+        self.instances(instances).max_by_key(|i| i.last_played())
+    }
+
+    /// The build that will most likely run with this location next.
+    pub fn version<'a>(&self, instances: &'a BTreeMap<String, Instance>) -> Option<&'a str> {
+        self.instance(instances).map(|i| i.version.as_str())
+    }
+}
+
+impl fmt::Display for ConfigLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shared => write!(f, "Shared"),
+            Self::Instance(name) => write!(f, "Instance: {name}"),
+        }
+    }
+}
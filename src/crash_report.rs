@@ -0,0 +1,103 @@
+use crate::get_data_dir;
+use crate::instance::Instance;
+use anyhow::Result;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::SystemTime;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// How many lines from the end of ESLauncher2.log are included in a report.
+const LAUNCHER_LOG_LINES: usize = 200;
+
+/// Bundles everything we know about a crashed session into `<log_dir>/<time>-crash.zip`,
+/// so it can be attached to a GitHub issue as-is.
+pub fn write(
+    instance: &Instance,
+    output: &Output,
+    log_dir: &Path,
+    time: &str,
+    started: SystemTime,
+) -> Result<PathBuf> {
+    let report_path = log_dir.join(format!("{time}-crash.zip"));
+    let mut zip = ZipWriter::new(File::create(&report_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("stdout.txt", options)?;
+    zip.write_all(&output.stdout)?;
+    zip.start_file("stderr.txt", options)?;
+    zip.write_all(&output.stderr)?;
+
+    // errors.txt is never cleared by the game, so only include it if this session touched it
+    if let Some(errors_file) = instance.config_dir().map(|dir| dir.join("errors.txt")) {
+        let modified = fs::metadata(&errors_file).and_then(|m| m.modified());
+        if modified.map(|m| m >= started).unwrap_or(false) {
+            zip.start_file("errors.txt", options)?;
+            zip.write_all(&fs::read(&errors_file)?)?;
+        }
+    }
+
+    zip.start_file("launcher.log", options)?;
+    zip.write_all(launcher_log_excerpt().as_bytes())?;
+
+    zip.start_file("instance.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, instance)?;
+
+    zip.start_file("system.txt", options)?;
+    zip.write_all(system_info(instance, output).as_bytes())?;
+
+    zip.finish()?;
+    Ok(report_path)
+}
+
+fn launcher_log_excerpt() -> String {
+    let Some(log_file) = get_data_dir().map(|dir| dir.join("ESLauncher2.log")) else {
+        return String::from("Failed to get data dir");
+    };
+    match fs::read_to_string(log_file) {
+        Ok(log) => {
+            let lines: Vec<&str> = log.lines().collect();
+            lines[lines.len().saturating_sub(LAUNCHER_LOG_LINES)..].join("\n")
+        }
+        Err(e) => format!("Failed to read launcher log: {e}"),
+    }
+}
+
+fn system_info(instance: &Instance, output: &Output) -> String {
+    let plugins = instance
+        .config_dir()
+        .map(|dir| dir.join("plugins"))
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| {
+            let mut names: Vec<String> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort_unstable();
+            names
+        })
+        .unwrap_or_default();
+
+    format!(
+        "ESLauncher2: v{}\nOS: {} ({}, {})\nInstance: {} ({:?})\nVersion: {}\nSource: {} {}\nExit status: {}\nActive plugins ({}):\n{}\n",
+        version!(),
+        std::env::consts::OS,
+        std::env::consts::FAMILY,
+        std::env::consts::ARCH,
+        instance.name,
+        instance.instance_type,
+        instance.version,
+        instance.source.r#type,
+        instance.source.identifier,
+        output.status,
+        plugins.len(),
+        plugins
+            .iter()
+            .map(|p| format!("  {p}"))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
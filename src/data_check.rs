@@ -0,0 +1,250 @@
+use crate::instance::Instance;
+use crate::style::{hint_text, text_button};
+use crate::Message;
+use anyhow::{Context, Result};
+use iced::widget::{Button, Column, Container, Row, Scrollable, Space, Text};
+use iced::{alignment, theme, Alignment, Color, Command, Element, Font, Length};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+/// Errors that don't point at a file of any plugin are attributed to this source.
+const BASE_GAME: &str = "Base game";
+
+/// Data errors from a single `--parse-assets` run, grouped by source (plugin or base game) and file.
+#[derive(Debug, Clone, Default)]
+pub struct DataCheckReport {
+    pub exit_status: String,
+    pub errors: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl DataCheckReport {
+    pub fn error_count(&self) -> usize {
+        self.errors
+            .values()
+            .flat_map(BTreeMap::values)
+            .map(Vec::len)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DataCheckPanel {
+    pub instance_name: String,
+    executable: PathBuf,
+    config_dir: Option<PathBuf>,
+    report: Option<Result<DataCheckReport, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DataCheckMessage {
+    Finished(Result<DataCheckReport, String>),
+    Rerun,
+}
+
+impl DataCheckPanel {
+    pub fn new(instance: &Instance) -> (Self, Command<Message>) {
+        let mut panel = Self {
+            instance_name: instance.name.clone(),
+            executable: instance.executable.clone(),
+            config_dir: instance.config_dir(),
+            report: None,
+        };
+        let cmd = panel.run();
+        (panel, cmd)
+    }
+
+    fn run(&mut self) -> Command<Message> {
+        self.report = None;
+        let executable = self.executable.clone();
+        let config_dir = self.config_dir.clone();
+        Command::perform(
+            async move {
+                let config_dir = config_dir.ok_or("Failed to get the game's config dir")?;
+                check_data(&executable, &config_dir).map_err(|e| format!("{e:#}"))
+            },
+            |report| Message::DataCheckMessage(DataCheckMessage::Finished(report)),
+        )
+    }
+
+    pub fn update(&mut self, message: DataCheckMessage) -> Command<Message> {
+        match message {
+            DataCheckMessage::Finished(report) => {
+                match &report {
+                    Ok(report) => info!(
+                        "Data check of {} found {} errors",
+                        self.instance_name,
+                        report.error_count()
+                    ),
+                    Err(e) => error!("Data check of {} failed: {}", self.instance_name, e),
+                }
+                self.report = Some(report);
+            }
+            DataCheckMessage::Rerun => return self.run(),
+        }
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<DataCheckMessage> {
+        let mut rerun_button = Button::new(Text::new("Check again").size(14)).style(text_button());
+        let body: Element<_> = match &self.report {
+            None => hint_text("Parsing game data...").into(),
+            Some(Err(e)) => {
+                rerun_button = rerun_button.on_press(DataCheckMessage::Rerun);
+                hint_text(format!("Failed to check data: {e}")).into()
+            }
+            Some(Ok(report)) => {
+                rerun_button = rerun_button.on_press(DataCheckMessage::Rerun);
+                if report.errors.is_empty() {
+                    hint_text(format!(
+                        "No data errors found (the game exited with {})",
+                        report.exit_status
+                    ))
+                    .into()
+                } else {
+                    Scrollable::new(report.errors.iter().fold(
+                        Column::new().spacing(10),
+                        |column, (source, files)| {
+                            let count: usize = files.values().map(Vec::len).sum();
+                            files.iter().fold(
+                                column.push(Text::new(format!("{source} ({count})")).size(20)),
+                                |column, (file, errors)| {
+                                    errors.iter().fold(
+                                        column.push(Text::new(file).size(14).style(
+                                            theme::Text::Color(Color::from_rgb(0.4, 0.4, 0.4)),
+                                        )),
+                                        |column, error| {
+                                            column.push(
+                                                Container::new(
+                                                    Text::new(error)
+                                                        .size(11)
+                                                        .font(Font::with_name("DejaVu Sans Mono"))
+                                                        .horizontal_alignment(
+                                                            alignment::Horizontal::Left,
+                                                        ),
+                                                )
+                                                .padding(iced::Padding {
+                                                    top: 0.0,
+                                                    right: 0.0,
+                                                    bottom: 0.0,
+                                                    left: 15.0,
+                                                }),
+                                            )
+                                        },
+                                    )
+                                },
+                            )
+                        },
+                    ))
+                    .height(Length::Fill)
+                    .into()
+                }
+            }
+        };
+
+        let summary = match &self.report {
+            Some(Ok(report)) => format!("{} errors", report.error_count()),
+            _ => String::new(),
+        };
+
+        Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(summary).size(14))
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(rerun_button),
+            )
+            .push(body)
+            .into()
+    }
+}
+
+/// Runs the game in `--parse-assets` mode and parses whatever it added to errors.txt.
+pub fn check_data(executable: &Path, config_dir: &Path) -> Result<DataCheckReport> {
+    let errors_file = config_dir.join("errors.txt");
+    let previous = fs::read_to_string(&errors_file).unwrap_or_default();
+    let started = SystemTime::now();
+
+    info!("Checking game data with {}", executable.to_string_lossy());
+    let output = process::Command::new(executable)
+        .arg("--config")
+        .arg(config_dir)
+        .arg("--parse-assets")
+        .output()
+        .with_context(|| "Failed to run the game")?;
+
+    let modified = fs::metadata(&errors_file)
+        .and_then(|m| m.modified())
+        .is_ok_and(|m| m >= started);
+    let errors = if modified {
+        let current = fs::read_to_string(&errors_file)?;
+        // The game may either append to or recreate errors.txt, only look at the new part
+        match current.strip_prefix(&previous) {
+            Some(appended) if !previous.is_empty() => appended.to_string(),
+            _ => current,
+        }
+    } else {
+        String::new()
+    };
+
+    Ok(DataCheckReport {
+        exit_status: output.status.to_string(),
+        errors: parse_errors(&errors, config_dir),
+    })
+}
+
+/// errors.txt consists of blank-line separated entries.
+/// Each describes the error in its first line(s), usually followed by a `file "<path>"` line
+/// and the offending data nodes, prefixed with their line numbers.
+fn parse_errors(
+    errors: &str,
+    config_dir: &Path,
+) -> BTreeMap<String, BTreeMap<String, Vec<String>>> {
+    let plugins_dir = config_dir
+        .join("plugins")
+        .to_string_lossy()
+        .replace('\\', "/");
+    let mut grouped: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for entry in errors
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        let file = entry.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("file ")
+                .map(|f| f.trim_matches('"').replace('\\', "/"))
+        });
+        let (source, file) = match file {
+            Some(file) => match file.strip_prefix(&plugins_dir) {
+                Some(in_plugin) => {
+                    let in_plugin = in_plugin.trim_start_matches('/');
+                    let (plugin, path) = in_plugin.split_once('/').unwrap_or((in_plugin, ""));
+                    (plugin.to_string(), path.to_string())
+                }
+                None => (
+                    BASE_GAME.to_string(),
+                    file.rsplit_once("/data/")
+                        .map_or(file.clone(), |(_, path)| format!("data/{path}")),
+                ),
+            },
+            None => (BASE_GAME.to_string(), String::from("(unknown file)")),
+        };
+        let description: Vec<&str> = entry
+            .lines()
+            .filter(|line| !line.trim().starts_with("file "))
+            .collect();
+        grouped
+            .entry(source)
+            .or_default()
+            .entry(file)
+            .or_default()
+            .push(description.join("\n"));
+    }
+    grouped
+}
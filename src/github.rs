@@ -157,12 +157,66 @@ pub fn get_git_ref(name: &str) -> Result<GitRef> {
     ))
 }
 
+#[derive(Deserialize, Debug)]
+pub struct Comparison {
+    /// `ahead`, `behind`, `identical` or `diverged`, seen from `head`
+    pub status: String,
+}
+
+/// Compares two refs (tags, branches or commit SHAs) of the game's repository.
+pub fn compare(base: &str, head: &str) -> Result<Comparison> {
+    make_request(&format!(
+        "https://api.github.com/repos/endless-sky/endless-sky/compare/{base}...{head}"
+    ))
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Release {
     pub id: i64,
     pub tag_name: String,
 }
 
+pub fn get_latest_release_of(repo_slug: &str) -> Result<Release> {
+    make_request(&format!(
+        "https://api.github.com/repos/{repo_slug}/releases/latest"
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Repository {
+    pub default_branch: String,
+}
+
+pub fn get_repository(repo_slug: &str) -> Result<Repository> {
+    make_request(&format!("https://api.github.com/repos/{repo_slug}"))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Commit {
+    pub sha: String,
+}
+
+pub fn get_commit(repo_slug: &str, reference: &str) -> Result<Commit> {
+    make_request(&format!(
+        "https://api.github.com/repos/{repo_slug}/commits/{reference}"
+    ))
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReleaseNotes {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub published_at: Option<String>,
+}
+
+/// The most recent releases of a repository, newest first.
+pub fn get_release_notes(repo_slug: &str) -> Result<Vec<ReleaseNotes>> {
+    make_request(&format!(
+        "https://api.github.com/repos/{repo_slug}/releases?per_page=5"
+    ))
+}
+
 pub fn get_release_by_tag(tag: &str) -> Result<Release> {
     make_request(&format!(
         "https://api.github.com/repos/endless-sky/endless-sky/releases/tags/{tag}"
@@ -0,0 +1,130 @@
+use crate::get_data_dir;
+use crate::plugins::{self, IndexEntry};
+use anyhow::{Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::SystemTime;
+
+/// Least recently used icons are evicted once the cache grows beyond this
+const MAX_CACHE_SIZE: u64 = 32 * 1024 * 1024;
+
+lazy_static! {
+    static ref CACHE_FILENAME_REGEX: Regex = Regex::new(r"[^\w.-]").unwrap();
+}
+
+fn get_cache_dir() -> Result<PathBuf> {
+    let cache_dir = get_data_dir()
+        .ok_or_else(|| anyhow!("Failed to get app data dir"))?
+        .join("icons");
+    if !(cache_dir.exists()) {
+        fs::create_dir(&cache_dir).with_context(|| "Failed to create icon cache")?;
+    }
+    Ok(cache_dir)
+}
+
+fn cache_file_name(entry: &IndexEntry) -> String {
+    let desired = format!("{}-{}", entry.name, entry.version);
+    CACHE_FILENAME_REGEX.replace_all(&desired, "_").into_owned()
+}
+
+/// The plugin's icon from the cache, downloading it first if necessary.
+pub fn load(entry: &IndexEntry) -> Result<Vec<u8>> {
+    let cache_file = get_cache_dir()?.join(cache_file_name(entry));
+    if cache_file.exists() && cache_file.is_file() {
+        let mut bytes = vec![];
+        let mut file = File::options().read(true).write(true).open(cache_file)?;
+        file.read_to_end(&mut bytes)?;
+        // The modification time doubles as the last use, for eviction
+        file.set_modified(SystemTime::now()).ok();
+        Ok(bytes)
+    } else {
+        let url = entry
+            .icon_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("{} has no icon", entry.name))?;
+        let bytes = plugins::retrieve_icon(url).with_context(|| "Failed to load icon from URL")?;
+        File::create(cache_file)?.write_all(&bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Loads the icons of all entries, running at most `concurrency` downloads at a time.
+/// `loaded` is called with each icon as soon as it's available.
+pub fn load_all(
+    entries: &[IndexEntry],
+    concurrency: usize,
+    loaded: impl Fn(&IndexEntry, Vec<u8>) + Sync,
+) {
+    let queue = Mutex::new(entries.iter());
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| loop {
+                let Some(entry) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                match load(entry) {
+                    Ok(icon) => loaded(entry, icon),
+                    Err(e) => debug!("failed to fetch icon: {:#}", e),
+                }
+            });
+        }
+    });
+}
+
+/// Removes icons of plugin versions that aren't in `entries` anymore,
+/// then the least recently used ones until the cache fits its size limit.
+pub fn prune(entries: &[IndexEntry]) -> Result<()> {
+    let referenced: HashSet<String> = entries.iter().map(cache_file_name).collect();
+    let mut kept = vec![];
+    let mut removed = 0;
+    for entry in fs::read_dir(get_cache_dir()?)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if referenced.contains(&name) {
+            let metadata = entry.metadata()?;
+            kept.push((metadata.modified()?, metadata.len(), entry.path()));
+        } else {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+
+    let mut size: u64 = kept.iter().map(|(_, len, _)| len).sum();
+    kept.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in kept {
+        if size <= MAX_CACHE_SIZE {
+            break;
+        }
+        fs::remove_file(path)?;
+        size -= len;
+        removed += 1;
+    }
+    if removed > 0 {
+        info!("Removed {} icons from the cache", removed);
+    }
+    Ok(())
+}
+
+/// The combined size of all cached icons in bytes.
+pub fn size() -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(get_cache_dir()?)? {
+        size += entry?.metadata()?.len();
+    }
+    Ok(size)
+}
+
+pub fn clear() -> Result<()> {
+    let cache_dir = get_cache_dir()?;
+    fs::remove_dir_all(&cache_dir)
+        .with_context(|| format!("Failed to remove {}", cache_dir.to_string_lossy()))?;
+    info!("Cleared the icon cache");
+    Ok(())
+}
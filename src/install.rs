@@ -11,6 +11,9 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 
+/// Directories inside an instance that belong to the user rather than the game build.
+const PRESERVED_DIRS: [&str; 2] = ["config", "logs"];
+
 pub fn install(
     destination: PathBuf,
     name: String,
@@ -38,7 +41,7 @@ pub fn install(
     }
 
     if destination.exists() {
-        fs::remove_dir_all(&destination)?;
+        clear_destination(&destination)?;
     }
     fs::create_dir_all(&destination)?;
 
@@ -101,6 +104,25 @@ pub fn install(
     ))
 }
 
+/// Removes a previous installation, but keeps the instance's own saves and logs around.
+fn clear_destination(destination: &Path) -> Result<()> {
+    for entry in fs::read_dir(destination)? {
+        let path = entry?.path();
+        if PRESERVED_DIRS
+            .iter()
+            .any(|dir| path.file_name().is_some_and(|name| name == *dir))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
 fn download_release_asset(
     instance_name: &str,
     tag: &str,
@@ -2,7 +2,7 @@ use crate::instance::{get_instances_dir, InstanceType};
 use crate::style::text_button;
 use crate::{instance, Message};
 use core::fmt;
-use iced::widget::{Button, Column, Container, Radio, Scrollable, Text, TextInput};
+use iced::widget::{Button, Checkbox, Column, Container, Radio, Scrollable, Text, TextInput};
 use iced::{alignment, Alignment, Command, Element, Length};
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +14,7 @@ const BLACKLISTED_CHARS: [char; 10] = ['/', '\\', ':', '*', '?', '"', '<', '>',
 pub struct InstallFrame {
     pub(crate) name: String,
     source: InstanceSource,
+    isolated_config: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +22,7 @@ pub enum InstallFrameMessage {
     SourceTypeChanged(InstanceSourceType),
     NameChanged(String),
     SourceIdentifierChanged(String),
+    IsolatedConfigToggled(bool),
     StartInstallation(InstanceType),
 }
 
@@ -68,6 +70,7 @@ impl InstallFrame {
                             self.name.clone(),
                             instance_type,
                             self.source.clone(),
+                            self.isolated_config,
                         ),
                         Message::Dummy,
                     );
@@ -86,6 +89,9 @@ impl InstallFrame {
             InstallFrameMessage::SourceIdentifierChanged(identifier) => {
                 self.source.identifier = identifier;
             }
+            InstallFrameMessage::IsolatedConfigToggled(isolated_config) => {
+                self.isolated_config = isolated_config;
+            }
         }
         Command::none()
     }
@@ -137,6 +143,13 @@ impl InstallFrame {
                         .padding(10),
                 )
                 .push(controls)
+                .push(
+                    Checkbox::new(
+                        "Separate saves, preferences & plugins",
+                        self.isolated_config,
+                    )
+                    .on_toggle(InstallFrameMessage::IsolatedConfigToggled),
+                )
                 .push(install_button)
                 .spacing(20)
                 .align_items(Alignment::End),
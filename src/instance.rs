@@ -1,8 +1,14 @@
 use crate::install_frame::InstanceSource;
+use crate::instance_panel::PanelType;
 use crate::music::MusicCommand;
+use crate::plugin_profiles::PluginProfiles;
+use crate::settings::Settings;
 use crate::style::icon_button;
-use crate::{get_data_dir, install, send_message, style, update, Message};
-use anyhow::Result;
+use crate::{
+    crash_report, get_data_dir, install, save_snapshots, send_message, style, trash, update,
+    Message,
+};
+use anyhow::{Context, Result};
 use iced::widget::{Button, Column, ProgressBar, Row, Space, Text};
 use iced::{alignment, theme, Alignment, Element, Length};
 use serde::{Deserialize, Serialize};
@@ -12,8 +18,12 @@ use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::SystemTime;
 use time::{format_description, OffsetDateTime};
 
+/// How many runs are kept per instance. Older ones only count towards the playtime.
+const MAX_SESSIONS: usize = 200;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum InstanceType {
     MacOS,
@@ -56,6 +66,36 @@ pub struct Instance {
     pub version: String,
     pub instance_type: InstanceType,
     pub source: InstanceSource,
+    /// Whether this instance keeps its saves, preferences and plugins in `<path>/config`
+    /// instead of sharing the game's regular config directory.
+    #[serde(default)]
+    pub isolated_config: bool,
+    /// The most recent runs, at most `MAX_SESSIONS`
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    /// Playtime in seconds of the sessions that were dropped to stay below `MAX_SESSIONS`
+    #[serde(default)]
+    pub earlier_playtime: i64,
+}
+
+/// A single run of an instance, as recorded by `play`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Unix timestamps
+    pub start: i64,
+    pub end: i64,
+    pub exit_status: String,
+    pub success: bool,
+    pub debug: bool,
+    /// The plugin profile that was active for this session, if any
+    pub profile: Option<String>,
+    pub crash_report: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn duration(&self) -> i64 {
+        (self.end - self.start).max(0)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -92,6 +132,33 @@ impl Progress {
         self.total_approx = total_approx;
         self
     }
+
+    /// The status, with a progress bar and counter if the amount of work is known.
+    pub fn view<'a, M: 'a>(&'a self) -> Column<'a, M> {
+        let mut status_field = Column::new().align_items(Alignment::Center).push(
+            Text::new(&self.status)
+                .size(16)
+                .horizontal_alignment(alignment::Horizontal::Center),
+        );
+        if let (Some(done), Some(total)) = (self.done, self.total) {
+            status_field = status_field
+                .push(ProgressBar::new(0.0..=total as f32, done as f32).height(Length::Fixed(5.)));
+        }
+        if let Some(done) = self.done {
+            status_field = status_field.push(
+                Text::new(format!(
+                    "{}/{}{}{}",
+                    done,
+                    if self.total_approx { "~" } else { "" },
+                    self.total.map_or_else(|| "?".into(), |u| u.to_string()),
+                    self.units.as_ref().unwrap_or(&String::new())
+                ))
+                .size(12)
+                .horizontal_alignment(alignment::Horizontal::Center),
+            );
+        }
+        status_field
+    }
 }
 
 impl<T: AsRef<str>> From<T> for Progress {
@@ -120,7 +187,10 @@ pub enum InstanceMessage {
     Play(bool),
     Update,
     Folder,
+    OpenPanel(PanelType),
     Delete,
+    OpenCrashReport,
+    PlayFinished(Option<Session>),
     StateChanged(InstanceState),
 }
 
@@ -142,10 +212,51 @@ impl Instance {
             version,
             instance_type,
             source,
+            isolated_config: false,
+            sessions: vec![],
+            earlier_playtime: 0,
+        }
+    }
+
+    pub fn last_played(&self) -> Option<i64> {
+        self.sessions.last().map(|s| s.end)
+    }
+
+    /// Total playtime in seconds
+    pub fn playtime(&self) -> i64 {
+        self.earlier_playtime + self.sessions.iter().map(Session::duration).sum::<i64>()
+    }
+
+    /// Records a finished run, dropping the oldest ones once there are too many
+    /// so instances.json doesn't keep growing.
+    fn add_session(&mut self, session: Session) {
+        self.sessions.push(session);
+        let excess = self.sessions.len().saturating_sub(MAX_SESSIONS);
+        self.earlier_playtime += self
+            .sessions
+            .drain(..excess)
+            .map(|s| s.duration())
+            .sum::<i64>();
+    }
+
+    /// The directory the game uses for preferences, saves, plugins and errors.txt when run from this instance.
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        if self.isolated_config {
+            Some(self.path.join("config"))
+        } else {
+            get_game_config_dir()
         }
     }
 
-    pub fn update(&mut self, message: InstanceMessage) -> iced::Command<Message> {
+    pub fn last_crash_report(&self) -> Option<&PathBuf> {
+        self.sessions.last().and_then(|s| s.crash_report.as_ref())
+    }
+
+    pub fn update(
+        &mut self,
+        message: InstanceMessage,
+        settings: &Settings,
+    ) -> iced::Command<Message> {
         match message {
             InstanceMessage::Play(do_debug) => {
                 let name1 = self.name.clone(); // (Jett voice)
@@ -159,17 +270,9 @@ impl Instance {
                         )
                     }),
                     iced::Command::perform(
-                        perform_play(
-                            self.path.clone(),
-                            self.executable.clone(),
-                            self.name.clone(),
-                            do_debug,
-                        ),
-                        move |()| {
-                            Message::InstanceMessage(
-                                name2,
-                                InstanceMessage::StateChanged(InstanceState::Ready),
-                            )
+                        perform_play(self.clone(), do_debug, settings.clone()),
+                        move |session| {
+                            Message::InstanceMessage(name2, InstanceMessage::PlayFinished(session))
                         },
                     ),
                 ])
@@ -191,12 +294,30 @@ impl Instance {
             InstanceMessage::Folder => {
                 iced::Command::perform(open_folder(self.path.clone()), Message::Dummy)
             }
+            InstanceMessage::OpenPanel(panel_type) => {
+                let name = self.name.clone();
+                iced::Command::perform(dummy(), move |()| Message::OpenPanel(name, panel_type))
+            }
             InstanceMessage::Delete => {
                 let name = self.name.clone();
-                iced::Command::perform(delete(self.path.clone()), move |_| {
-                    Message::RemoveInstance(Some(name))
+                iced::Command::perform(dummy(), move |()| {
+                    Message::OpenPanel(name, PanelType::ConfirmDelete)
                 })
             }
+            InstanceMessage::OpenCrashReport => match self.last_crash_report() {
+                Some(report) => iced::Command::perform(open_folder(report.clone()), Message::Dummy),
+                None => iced::Command::none(),
+            },
+            InstanceMessage::PlayFinished(session) => {
+                self.state = InstanceState::Ready;
+                match session {
+                    Some(session) => {
+                        self.add_session(session);
+                        iced::Command::perform(dummy(), |()| Message::SaveInstances)
+                    }
+                    None => iced::Command::none(),
+                }
+            }
             InstanceMessage::StateChanged(state) => {
                 self.state = state;
                 iced::Command::none()
@@ -212,12 +333,29 @@ impl Instance {
         let folder_button = Button::new(style::folder_icon())
             .style(icon_button())
             .on_press(InstanceMessage::Folder);
+        let logs_button = Button::new(style::logs_icon())
+            .style(icon_button())
+            .on_press(InstanceMessage::OpenPanel(PanelType::Logs));
+        let mut tests_button = Button::new(style::tests_icon()).style(icon_button());
+        let mut check_data_button = Button::new(style::check_data_icon()).style(icon_button());
+        let mut rename_button = Button::new(style::rename_icon()).style(icon_button());
+        let mut clone_button = Button::new(style::copy_icon()).style(icon_button());
         let mut delete_button = Button::new(style::delete_icon()).style(theme::Button::Destructive);
+        let crash_report_button = self.last_crash_report().map(|_| {
+            Button::new(style::crash_icon())
+                .style(icon_button())
+                .on_press(InstanceMessage::OpenCrashReport)
+        });
 
         if self.state.is_ready() {
             debug_button = debug_button.on_press(InstanceMessage::Play(true));
             play_button = play_button.on_press(InstanceMessage::Play(false));
             update_button = update_button.on_press(InstanceMessage::Update);
+            tests_button = tests_button.on_press(InstanceMessage::OpenPanel(PanelType::Tests));
+            check_data_button =
+                check_data_button.on_press(InstanceMessage::OpenPanel(PanelType::DataCheck));
+            rename_button = rename_button.on_press(InstanceMessage::OpenPanel(PanelType::Rename));
+            clone_button = clone_button.on_press(InstanceMessage::OpenPanel(PanelType::Clone));
             delete_button = delete_button.on_press(InstanceMessage::Delete);
         }
 
@@ -237,45 +375,48 @@ impl Instance {
                             self.source.r#type, self.source.identifier
                         ))
                         .size(10),
+                    )
+                    .push(
+                        Text::new(if self.isolated_config {
+                            "Config: isolated"
+                        } else {
+                            "Config: shared"
+                        })
+                        .size(10),
+                    )
+                    .push(
+                        Text::new(match self.last_played() {
+                            Some(last_played) => format!(
+                                "Last played: {} (Playtime: {})",
+                                format_timestamp(last_played),
+                                format_duration(self.playtime())
+                            ),
+                            None => String::from("Never played"),
+                        })
+                        .size(10),
                     ),
             )
             .push(Space::new(Length::Fill, Length::Shrink))
             .push({
                 if let InstanceState::Working(progress) = &self.state {
-                    let mut status_field = Column::new().align_items(Alignment::Center).push(
-                        Text::new(&progress.status)
-                            .size(16)
-                            .horizontal_alignment(alignment::Horizontal::Center),
-                    );
-                    if let (Some(done), Some(total)) = (progress.done, progress.total) {
-                        status_field = status_field.push(
-                            ProgressBar::new(0.0..=total as f32, done as f32)
-                                .height(Length::Fixed(5.)),
-                        );
-                    }
-                    if let Some(done) = progress.done {
-                        status_field = status_field.push(
-                            Text::new(format!(
-                                "{}/{}{}{}",
-                                done,
-                                if progress.total_approx { "~" } else { "" },
-                                progress.total.map_or_else(|| "?".into(), |u| u.to_string()),
-                                progress.units.as_ref().unwrap_or(&String::new())
-                            ))
-                            .size(12)
-                            .horizontal_alignment(alignment::Horizontal::Center),
-                        );
-                    }
                     Row::new()
                         .push(Space::with_width(Length::FillPortion(1)))
-                        .push(status_field.width(Length::FillPortion(2)))
+                        .push(progress.view().width(Length::FillPortion(2)))
                 } else {
-                    Row::new()
-                        .spacing(10)
+                    let mut controls = Row::new().spacing(10);
+                    if let Some(crash_report_button) = crash_report_button {
+                        controls = controls.push(crash_report_button);
+                    }
+                    controls
                         .push(debug_button)
                         .push(play_button)
                         .push(update_button)
+                        .push(check_data_button)
+                        .push(tests_button)
+                        .push(logs_button)
                         .push(folder_button)
+                        .push(rename_button)
+                        .push(clone_button)
                         .push(delete_button)
                 }
             })
@@ -290,6 +431,7 @@ pub async fn perform_install(
     name: String,
     instance_type: InstanceType,
     instance_source: InstanceSource,
+    isolated_config: bool,
 ) {
     send_message(Message::AddInstance(Box::new(Instance::new(
         path.clone(),
@@ -301,7 +443,8 @@ pub async fn perform_install(
         InstanceState::Working(Progress::default()),
     ))));
     match install::install(path, name.clone(), instance_type, instance_source) {
-        Ok(instance) => {
+        Ok(mut instance) => {
+            instance.isolated_config = isolated_config;
             send_message(Message::AddInstance(Box::new(instance)));
         }
         Err(e) => {
@@ -318,20 +461,33 @@ pub async fn open_folder(path: PathBuf) {
     }
 }
 
-pub async fn delete(path: PathBuf) -> Option<PathBuf> {
-    if fs::remove_dir_all(&path).is_ok() {
-        info!("Removed {}", path.to_string_lossy());
-        Some(path)
-    } else {
-        error!("Failed to remove {}", path.to_string_lossy());
-        None
+/// Moves the instance to the trash, returning its name if that worked.
+pub async fn delete(instance: Instance) -> Option<String> {
+    let description = format!("Instance {}", instance.name);
+    let name = instance.name.clone();
+    let path = instance.path.clone();
+    match trash::put(description, vec![path.clone()], Some(Box::new(instance))) {
+        Ok(_) => Some(name),
+        Err(e) => {
+            error!("Failed to remove {}: {:#}", path.to_string_lossy(), e);
+            None
+        }
     }
 }
 
 pub async fn perform_update(instance: Instance) {
     let name = instance.name.clone();
+    let isolated_config = instance.isolated_config;
+    let sessions = instance.sessions.clone();
+    let earlier_playtime = instance.earlier_playtime;
     match update::update_instance(instance).await {
-        Ok(instance) => send_message(Message::AddInstance(Box::new(instance))),
+        Ok(mut instance) => {
+            // Reinstalls create a fresh Instance, so carry over what the launcher knows about it
+            instance.isolated_config = isolated_config;
+            instance.sessions = sessions;
+            instance.earlier_playtime = earlier_playtime;
+            send_message(Message::AddInstance(Box::new(instance)));
+        }
         Err(e) => {
             error!("Failed to update instance: {:#}", e);
             send_message(Message::InstanceMessage(
@@ -342,16 +498,24 @@ pub async fn perform_update(instance: Instance) {
     }
 }
 
-pub async fn perform_play(path: PathBuf, executable: PathBuf, name: String, do_debug: bool) {
+pub async fn perform_play(
+    instance: Instance,
+    do_debug: bool,
+    settings: Settings,
+) -> Option<Session> {
     send_message(Message::MusicMessage(MusicCommand::WeakPause));
-    if let Err(e) = play(path, executable, name, do_debug).await {
-        error!("Failed to run game: {:#}", e);
-    }
+    let session = play(&instance, do_debug, &settings)
+        .await
+        .map_err(|e| error!("Failed to run game: {:#}", e))
+        .ok();
     send_message(Message::MusicMessage(MusicCommand::WeakPlay));
+    session
 }
 
-pub async fn play(path: PathBuf, executable: PathBuf, name: String, do_debug: bool) -> Result<()> {
-    let mut log_path = path;
+/// Snapshots the saves, runs the instance and writes its output to `<instance>/logs`.
+/// If the game exits unsuccessfully, a crash report is written alongside the logs.
+pub async fn play(instance: &Instance, do_debug: bool, settings: &Settings) -> Result<Session> {
+    let mut log_path = instance.path.clone();
     log_path.push("logs");
     fs::create_dir_all(&log_path)?;
 
@@ -368,36 +532,91 @@ pub async fn play(path: PathBuf, executable: PathBuf, name: String, do_debug: bo
 
     info!(
         "Launching {} via executable {}",
-        name,
-        executable.to_string_lossy()
+        instance.name,
+        instance.executable.to_string_lossy()
     );
 
-    let mut cmd = Command::new(&executable);
+    if let Some(saves_dir) = instance.config_dir().map(|dir| dir.join("saves")) {
+        if let Err(e) = save_snapshots::snapshot_saves(
+            &saves_dir,
+            &instance.name,
+            &instance.version,
+            settings.save_snapshot_count,
+        ) {
+            error!("Failed to snapshot saves: {:#}", e);
+        }
+    }
+
+    let profile = instance.config_dir().and_then(|dir| {
+        PluginProfiles::load()
+            .active(&dir.join("plugins"))
+            .map(|p| p.name.clone())
+    });
+
+    let started = SystemTime::now();
+    let start = OffsetDateTime::now_utc().unix_timestamp();
+    let mut cmd = Command::new(&instance.executable);
+    if instance.isolated_config {
+        let config_dir = instance.path.join("config");
+        fs::create_dir_all(&config_dir)?;
+        cmd.arg("--config").arg(config_dir);
+    }
     let output = if do_debug {
         cmd.arg("-d").output()
     } else {
         cmd.output()
+    }
+    .with_context(|| "Error starting process")?;
+
+    let mut session = Session {
+        start,
+        end: OffsetDateTime::now_utc().unix_timestamp(),
+        exit_status: output.status.to_string(),
+        success: output.status.success(),
+        debug: do_debug,
+        profile,
+        crash_report: None,
     };
-    match output {
-        Ok(output) => {
-            info!("{} exited with {}", name, output.status);
-            out.write_all(&output.stdout)?;
-            err.write_all(&output.stderr)?;
-            info!(
-                "Logfiles have been written to {}",
-                log_path.to_string_lossy()
-            );
-            if !output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                error!("Stdout was: {}", stdout);
-                error!("Stderr was: {}", stderr);
+    info!("{} exited with {}", instance.name, output.status);
+    out.write_all(&output.stdout)?;
+    err.write_all(&output.stderr)?;
+    info!(
+        "Logfiles have been written to {}",
+        log_path.to_string_lossy()
+    );
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!("Stdout was: {}", stdout);
+        error!("Stderr was: {}", stderr);
+
+        match crash_report::write(instance, &output, &log_path, &time, started) {
+            Ok(report) => {
+                warn!(
+                    "{} crashed, a crash report has been written to {}",
+                    instance.name,
+                    report.to_string_lossy()
+                );
+                session.crash_report = Some(report);
             }
+            Err(e) => error!("Failed to write crash report: {:#}", e),
         }
+    }
+    Ok(session)
+}
 
-        Err(e) => error!("Error starting process: {}", e),
-    };
-    Ok(())
+pub fn format_timestamp(timestamp: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|t| {
+            t.format(&format_description::parse("[year]-[month]-[day] [hour]:[minute] UTC").ok()?)
+                .ok()
+        })
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+pub fn format_duration(seconds: i64) -> String {
+    format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
 }
 
 pub fn get_instances_dir() -> Option<PathBuf> {
@@ -406,6 +625,11 @@ pub fn get_instances_dir() -> Option<PathBuf> {
     Some(dir)
 }
 
+/// The directory the game itself keeps its preferences, saves, plugins and errors.txt in.
+pub fn get_game_config_dir() -> Option<PathBuf> {
+    Some(platform_dirs::AppDirs::new(Some("endless-sky"), false)?.data_dir)
+}
+
 #[derive(Serialize, Deserialize)]
 struct InstancesContainer(Vec<Instance>);
 
@@ -415,18 +639,21 @@ pub fn perform_save_instances(instances: BTreeMap<String, Instance>) {
     };
 }
 
-fn save_instances(instances: BTreeMap<String, Instance>) -> Result<()> {
+/// Writes to a temporary file first, so a failed save never leaves a truncated instances.json behind.
+pub fn save_instances(instances: BTreeMap<String, Instance>) -> Result<()> {
     let mut instances_file =
         get_instances_dir().ok_or_else(|| anyhow!("Failed to get Instances dir"))?;
     instances_file.push("instances.json");
     debug!("Saving to {}", instances_file.to_string_lossy());
 
-    let file = File::create(instances_file)?;
+    let temp_file = instances_file.with_extension("json.tmp");
+    let file = File::create(&temp_file)?;
 
     serde_json::to_writer_pretty(
         file,
         &InstancesContainer(instances.values().cloned().collect()),
     )?;
+    fs::rename(temp_file, instances_file)?;
     Ok(())
 }
 
@@ -0,0 +1,127 @@
+use crate::data_check::DataCheckPanel;
+use crate::instance::Instance;
+use crate::instance_rename::{RenameMode, RenamePanel};
+use crate::integration_tests::TestsPanel;
+use crate::log_browser::LogBrowser;
+use crate::settings::Settings;
+use crate::style::icon_button;
+use crate::trash::{DeleteConfirmation, TrashPanel};
+use crate::{style, Message};
+use iced::widget::{Button, Column, Container, Row, Space, Text};
+use iced::{Alignment, Command, Element, Length};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelType {
+    Logs,
+    Tests,
+    DataCheck,
+    ConfirmDelete,
+    Rename,
+    Clone,
+}
+
+/// A per-instance view that temporarily replaces the install frame next to the instance list.
+#[derive(Debug, Clone)]
+pub enum InstancePanel {
+    Logs(LogBrowser),
+    Tests(TestsPanel),
+    DataCheck(DataCheckPanel),
+    ConfirmDelete(DeleteConfirmation),
+    Rename(RenamePanel),
+    /// Not tied to an instance, deleted instances and plugins end up here
+    Trash(TrashPanel),
+}
+
+impl InstancePanel {
+    pub fn open(
+        panel_type: PanelType,
+        instance: &Instance,
+        settings: &Settings,
+    ) -> (Self, Command<Message>) {
+        match panel_type {
+            PanelType::Logs => {
+                let (browser, cmd) = LogBrowser::new(instance, settings.log_retention_days);
+                (Self::Logs(browser), cmd)
+            }
+            PanelType::Tests => {
+                let (panel, cmd) = TestsPanel::new(instance);
+                (Self::Tests(panel), cmd)
+            }
+            PanelType::DataCheck => {
+                let (panel, cmd) = DataCheckPanel::new(instance);
+                (Self::DataCheck(panel), cmd)
+            }
+            PanelType::ConfirmDelete => {
+                let (panel, cmd) = DeleteConfirmation::new(instance, settings.trash_retention_days);
+                (Self::ConfirmDelete(panel), cmd)
+            }
+            PanelType::Rename => (
+                Self::Rename(RenamePanel::new(instance, RenameMode::Rename)),
+                Command::none(),
+            ),
+            PanelType::Clone => (
+                Self::Rename(RenamePanel::new(instance, RenameMode::Clone)),
+                Command::none(),
+            ),
+        }
+    }
+
+    pub fn open_trash(settings: &Settings) -> (Self, Command<Message>) {
+        let (panel, cmd) = TrashPanel::new(settings.trash_retention_days);
+        (Self::Trash(panel), cmd)
+    }
+
+    pub fn instance_name(&self) -> Option<&str> {
+        match self {
+            Self::Logs(browser) => Some(&browser.instance_name),
+            Self::Tests(panel) => Some(&panel.instance_name),
+            Self::DataCheck(panel) => Some(&panel.instance_name),
+            Self::ConfirmDelete(panel) => Some(&panel.instance_name),
+            Self::Rename(panel) => Some(&panel.instance_name),
+            Self::Trash(_) => None,
+        }
+    }
+
+    fn title(&self) -> String {
+        let name = self.instance_name().unwrap_or_default();
+        match self {
+            Self::Logs(_) => format!("Logs: {name}"),
+            Self::Tests(_) => format!("Tests: {name}"),
+            Self::DataCheck(_) => format!("Data check: {name}"),
+            Self::ConfirmDelete(_) => format!("Delete: {name}"),
+            Self::Rename(panel) if panel.mode == RenameMode::Clone => format!("Clone: {name}"),
+            Self::Rename(_) => format!("Rename: {name}"),
+            Self::Trash(_) => String::from("Trash"),
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let content = match self {
+            Self::Logs(browser) => browser.view().map(Message::LogBrowserMessage),
+            Self::Tests(panel) => panel.view().map(Message::TestsMessage),
+            Self::DataCheck(panel) => panel.view().map(Message::DataCheckMessage),
+            Self::ConfirmDelete(panel) => panel.view().map(Message::DeleteMessage),
+            Self::Rename(panel) => panel.view().map(Message::RenameMessage),
+            Self::Trash(panel) => panel.view().map(Message::TrashMessage),
+        };
+        Container::new(
+            Column::new()
+                .padding(20)
+                .spacing(20)
+                .push(
+                    Row::new()
+                        .align_items(Alignment::Center)
+                        .push(Text::new(self.title()).size(26))
+                        .push(Space::new(Length::Fill, Length::Shrink))
+                        .push(
+                            Button::new(style::close_icon())
+                                .style(icon_button())
+                                .on_press(Message::ClosePanel),
+                        ),
+                )
+                .push(content),
+        )
+        .width(Length::FillPortion(3))
+        .into()
+    }
+}
@@ -0,0 +1,216 @@
+use crate::instance::{get_instances_dir, Instance};
+use crate::style::{hint_text, text_button};
+use anyhow::{Context, Result};
+use fs_extra::dir::CopyOptions;
+use iced::widget::{Button, Column, Row, Text, TextInput};
+use iced::{theme, Alignment, Color, Element, Length};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameMode {
+    Rename,
+    Clone,
+}
+
+/// Asks for a new name for an instance, or for a copy of it.
+#[derive(Debug, Clone)]
+pub struct RenamePanel {
+    pub instance_name: String,
+    pub mode: RenameMode,
+    new_name: String,
+    working: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RenameMessage {
+    NameInput(String),
+    Submit,
+    Done(Result<Box<Instance>, String>),
+}
+
+impl RenamePanel {
+    pub fn new(instance: &Instance, mode: RenameMode) -> Self {
+        Self {
+            instance_name: instance.name.clone(),
+            mode,
+            new_name: match mode {
+                RenameMode::Rename => instance.name.clone(),
+                RenameMode::Clone => format!("{} copy", instance.name),
+            },
+            working: false,
+            error: None,
+        }
+    }
+
+    pub fn new_name(&self) -> &str {
+        self.new_name.trim()
+    }
+
+    pub fn update(&mut self, message: RenameMessage) {
+        match message {
+            RenameMessage::NameInput(name) => {
+                self.new_name = name;
+                self.error = None;
+            }
+            RenameMessage::Submit => {
+                self.working = true;
+                self.error = None;
+            }
+            RenameMessage::Done(result) => {
+                self.working = false;
+                if let Err(e) = result {
+                    self.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Shows why the name can't be used, without starting any work.
+    pub fn reject(&mut self, reason: String) {
+        self.error = Some(reason);
+    }
+
+    pub fn view(&self) -> Element<RenameMessage> {
+        let mut submit = Button::new(
+            Text::new(match (self.mode, self.working) {
+                (RenameMode::Rename, false) => "Rename",
+                (RenameMode::Rename, true) => "Renaming...",
+                (RenameMode::Clone, false) => "Clone",
+                (RenameMode::Clone, true) => "Cloning...",
+            })
+            .size(14),
+        )
+        .style(text_button());
+        if !self.working && !self.new_name().is_empty() {
+            submit = submit.on_press(RenameMessage::Submit);
+        }
+        let mut input = TextInput::new("New name", &self.new_name)
+            .padding(5)
+            .size(14);
+        if !self.working {
+            input = input
+                .on_input(RenameMessage::NameInput)
+                .on_submit(RenameMessage::Submit);
+        }
+        Column::new()
+            .spacing(10)
+            .push(hint_text(match self.mode {
+                RenameMode::Rename => {
+                    "The instance folder is moved along with the name, including isolated saves and logs"
+                }
+                RenameMode::Clone => {
+                    "Copies the instance with all its files, e.g. to keep the current build around before updating"
+                }
+            }))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(input.width(Length::Fill))
+                    .push(submit),
+            )
+            .push_maybe(self.error.as_ref().map(|e| {
+                Text::new(e)
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.3, 0.3)))
+            }))
+            .into()
+    }
+}
+
+/// The folder a new instance with this name would get, if the name is free.
+pub fn check_name(name: &str, instances: &BTreeMap<String, Instance>) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\', ':']) || name == "." || name == ".." {
+        bail!("'{}' is not a valid instance name", name);
+    }
+    if instances.contains_key(name) {
+        bail!("There already is an instance named {}", name);
+    }
+    let path = get_instances_dir()
+        .ok_or_else(|| anyhow!("Failed to get Instances dir"))?
+        .join(name);
+    if path.exists() {
+        bail!("{} already exists", path.to_string_lossy());
+    }
+    Ok(path)
+}
+
+/// Points the paths the instance keeps below its folder to `new_path`.
+fn relocate(instance: &mut Instance, new_path: &Path) {
+    let old_path = instance.path.clone();
+    let moved = |path: &Path| match path.strip_prefix(&old_path) {
+        Ok(relative) => new_path.join(relative),
+        Err(_) => path.to_path_buf(),
+    };
+    let executable = moved(&instance.executable);
+    instance.executable = executable;
+    for session in &mut instance.sessions {
+        session.crash_report = session.crash_report.as_deref().map(moved);
+    }
+    instance.path = new_path.to_path_buf();
+}
+
+/// Moves the instance to its new folder. Nothing has changed on disk if this fails,
+/// instances.json only needs updating once it succeeded.
+pub async fn rename(instance: Instance, new_name: String, new_path: PathBuf) -> Result<Instance> {
+    fs::rename(&instance.path, &new_path).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            instance.path.to_string_lossy(),
+            new_path.to_string_lossy()
+        )
+    })?;
+    info!("Renamed instance {} to {}", instance.name, new_name);
+    let mut renamed = instance;
+    relocate(&mut renamed, &new_path);
+    renamed.name = new_name;
+    Ok(renamed)
+}
+
+/// Copies the instance to a new folder. The files are copied rather than hard-linked,
+/// because updates patch the archive and extract over the existing files in place,
+/// which would change the copy too.
+pub async fn duplicate(
+    instance: Instance,
+    new_name: String,
+    new_path: PathBuf,
+) -> Result<Instance> {
+    fs::create_dir_all(&new_path)?;
+    let options = CopyOptions {
+        content_only: true,
+        ..CopyOptions::new()
+    };
+    if let Err(e) = fs_extra::dir::copy(&instance.path, &new_path, &options) {
+        // Don't leave half a copy behind
+        fs::remove_dir_all(&new_path).ok();
+        return Err(e).with_context(|| {
+            format!(
+                "Failed to copy {} to {}",
+                instance.path.to_string_lossy(),
+                new_path.to_string_lossy()
+            )
+        });
+    }
+    info!("Cloned instance {} as {}", instance.name, new_name);
+    let mut copy = instance;
+    relocate(&mut copy, &new_path);
+    copy.name = new_name;
+    Ok(copy)
+}
+
+pub async fn perform(
+    mode: RenameMode,
+    instance: Instance,
+    new_name: String,
+    new_path: PathBuf,
+) -> Result<Box<Instance>, String> {
+    match mode {
+        RenameMode::Rename => rename(instance, new_name, new_path).await,
+        RenameMode::Clone => duplicate(instance, new_name, new_path).await,
+    }
+    .map(Box::new)
+    .map_err(|e| format!("{e:#}"))
+}
@@ -1,12 +1,36 @@
 use crate::instance::{load_instances, Instance};
+use crate::style::text_button;
 use crate::Message;
-use iced::widget::{Column, Container, Scrollable, Text};
+use core::fmt;
+use iced::widget::{Button, Column, Container, PickList, Row, Scrollable, Space, Text};
 use iced::{alignment, theme, Alignment, Color, Element, Length};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct InstancesFrame {
     pub instances: BTreeMap<String, Instance>,
+    pub sort: InstanceSort,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstanceSort {
+    #[default]
+    Name,
+    LastPlayed,
+}
+
+impl InstanceSort {
+    pub const ALL: [Self; 2] = [Self::Name, Self::LastPlayed];
+}
+
+impl fmt::Display for InstanceSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "Name"),
+            Self::LastPlayed => write!(f, "Last played"),
+        }
+    }
 }
 
 impl Default for InstancesFrame {
@@ -24,10 +48,23 @@ impl Default for InstancesFrame {
                 BTreeMap::new()
             }
         };
-        Self { instances }
+        Self {
+            instances,
+            sort: InstanceSort::default(),
+        }
     }
 }
 impl InstancesFrame {
+    /// Instances in the order they should be displayed in.
+    /// The map is already ordered by name, so sorting by last played is stable w.r.t. names.
+    fn sorted(&self) -> Vec<&Instance> {
+        let mut instances: Vec<&Instance> = self.instances.values().collect();
+        if self.sort == InstanceSort::LastPlayed {
+            instances.sort_by_key(|i| std::cmp::Reverse(i.last_played()));
+        }
+        instances
+    }
+
     pub fn view(&self) -> Element<Message> {
         let instances_column = Column::new()
             .padding(20)
@@ -50,8 +87,8 @@ impl InstancesFrame {
                 )
                 .into()
         } else {
-            self.instances
-                .values()
+            self.sorted()
+                .into_iter()
                 .fold(instances_column, |column, instance| {
                     column
                         .push(
@@ -80,6 +117,26 @@ impl InstancesFrame {
                         .horizontal_alignment(alignment::Horizontal::Center)
                         .width(Length::Fill),
                 )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            Button::new(Text::new("Trash").size(14))
+                                .style(text_button())
+                                .on_press(Message::OpenTrash),
+                        )
+                        .push(Space::new(Length::Fill, Length::Shrink))
+                        .push(Text::new("Sort by").size(14))
+                        .push(
+                            PickList::new(
+                                &InstanceSort::ALL[..],
+                                Some(self.sort),
+                                Message::InstanceSortChanged,
+                            )
+                            .text_size(14),
+                        ),
+                )
                 .push(instances_list)
                 .spacing(20)
                 .width(Length::Fill),
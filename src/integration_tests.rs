@@ -0,0 +1,479 @@
+use crate::instance::{load_instances, Instance};
+use crate::style::{hint_text, icon_button, text_button};
+use crate::{logger, style, Message};
+use anyhow::{Context, Result};
+use iced::widget::{Button, Checkbox, Column, Container, Row, Scrollable, Space, Text};
+use iced::{alignment, theme, Alignment, Color, Command, Element, Font, Length};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+/// A test as reported by the game's `--tests` flag.
+#[derive(Debug, Clone)]
+pub struct GameTest {
+    pub name: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    pub exit_status: String,
+    pub output: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+enum TestState {
+    Idle,
+    Queued,
+    Running,
+    Done(TestResult),
+}
+
+#[derive(Debug, Clone)]
+struct TestRow {
+    test: GameTest,
+    selected: bool,
+    state: TestState,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestsPanel {
+    pub instance_name: String,
+    executable: PathBuf,
+    config_dir: PathBuf,
+    tests: Option<Result<Vec<TestRow>, String>>,
+    queue: VecDeque<usize>,
+    headless: bool,
+    expanded: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TestsMessage {
+    Listed(Result<Vec<GameTest>, String>),
+    Toggle(usize, bool),
+    HeadlessToggled(bool),
+    RunAll,
+    RunSelected,
+    Finished(usize, TestResult),
+    ShowOutput(usize),
+}
+
+impl TestsPanel {
+    pub fn new(instance: &Instance) -> (Self, Command<Message>) {
+        let executable = instance.executable.clone();
+        (
+            Self {
+                instance_name: instance.name.clone(),
+                executable: executable.clone(),
+                config_dir: instance.path.join("tests-config"),
+                tests: None,
+                queue: VecDeque::new(),
+                headless: cfg!(target_os = "linux"),
+                expanded: None,
+            },
+            Command::perform(
+                async move { list_tests(&executable).map_err(|e| format!("{e:#}")) },
+                |tests| Message::TestsMessage(TestsMessage::Listed(tests)),
+            ),
+        )
+    }
+
+    pub fn update(&mut self, message: TestsMessage) -> Command<Message> {
+        match message {
+            TestsMessage::Listed(tests) => {
+                if let Err(e) = &tests {
+                    error!("Failed to list tests of {}: {}", self.instance_name, e);
+                }
+                self.tests = Some(tests.map(|tests| {
+                    tests
+                        .into_iter()
+                        .map(|test| TestRow {
+                            test,
+                            selected: false,
+                            state: TestState::Idle,
+                        })
+                        .collect()
+                }));
+            }
+            TestsMessage::Toggle(index, selected) => {
+                if let Some(Ok(rows)) = &mut self.tests {
+                    if let Some(row) = rows.get_mut(index) {
+                        row.selected = selected;
+                    }
+                }
+            }
+            TestsMessage::HeadlessToggled(headless) => self.headless = headless,
+            TestsMessage::RunAll | TestsMessage::RunSelected => {
+                let only_selected = matches!(message, TestsMessage::RunSelected);
+                if let Some(Ok(rows)) = &mut self.tests {
+                    for (index, row) in rows.iter_mut().enumerate() {
+                        let pending =
+                            self.queue.contains(&index) || matches!(row.state, TestState::Running);
+                        if (!only_selected || row.selected) && !pending {
+                            row.state = TestState::Queued;
+                            self.queue.push_back(index);
+                        }
+                    }
+                }
+                if !self.is_running() {
+                    return self.run_next();
+                }
+            }
+            TestsMessage::Finished(index, result) => {
+                if let Some(Ok(rows)) = &mut self.tests {
+                    if let Some(row) = rows.get_mut(index) {
+                        row.state = TestState::Done(result);
+                    }
+                }
+                return self.run_next();
+            }
+            TestsMessage::ShowOutput(index) => {
+                self.expanded = if self.expanded == Some(index) {
+                    None
+                } else {
+                    Some(index)
+                };
+            }
+        }
+        Command::none()
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(&self.tests, Some(Ok(rows)) if rows.iter().any(|r| matches!(r.state, TestState::Running)))
+    }
+
+    fn run_next(&mut self) -> Command<Message> {
+        let Some(Ok(rows)) = &mut self.tests else {
+            return Command::none();
+        };
+        let Some(index) = self.queue.pop_front() else {
+            return Command::none();
+        };
+        let Some(row) = rows.get_mut(index) else {
+            return Command::none();
+        };
+        row.state = TestState::Running;
+        let executable = self.executable.clone();
+        let config_dir = self.config_dir.clone();
+        let name = row.test.name.clone();
+        let headless = self.headless;
+        Command::perform(
+            async move { run_test(&executable, &config_dir, &name, headless) },
+            move |result| Message::TestsMessage(TestsMessage::Finished(index, result)),
+        )
+    }
+
+    pub fn view(&self) -> Element<TestsMessage> {
+        let rows = match &self.tests {
+            None => return hint_text("Querying tests...").into(),
+            Some(Err(e)) => return hint_text(format!("Failed to list tests: {e}")).into(),
+            Some(Ok(rows)) => rows,
+        };
+        if rows.is_empty() {
+            return hint_text("This build doesn't ship any tests").into();
+        }
+
+        let list =
+            rows.iter()
+                .enumerate()
+                .fold(Column::new().spacing(5), |column, (index, row)| {
+                    let (status, color) = match &row.state {
+                        TestState::Idle => {
+                            (row.test.status.clone(), Color::from_rgb(0.6, 0.6, 0.6))
+                        }
+                        TestState::Queued => ("queued".into(), Color::from_rgb(0.6, 0.6, 0.6)),
+                        TestState::Running => ("running".into(), Color::from_rgb(0.2, 0.2, 0.8)),
+                        TestState::Done(result) if result.passed => (
+                            format!("passed ({:.1}s)", result.duration.as_secs_f32()),
+                            Color::from_rgb(0.2, 0.6, 0.2),
+                        ),
+                        TestState::Done(result) => (
+                            format!("failed ({:.1}s)", result.duration.as_secs_f32()),
+                            Color::from_rgb(0.8, 0.2, 0.2),
+                        ),
+                    };
+                    let mut output_button = Button::new(style::logs_icon()).style(icon_button());
+                    if let TestState::Done(_) = row.state {
+                        output_button = output_button.on_press(TestsMessage::ShowOutput(index));
+                    }
+                    let column = column.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(
+                                Checkbox::new(&row.test.name, row.selected)
+                                    .on_toggle(move |selected| {
+                                        TestsMessage::Toggle(index, selected)
+                                    })
+                                    .text_size(14),
+                            )
+                            .push(Space::new(Length::Fill, Length::Shrink))
+                            .push(Text::new(status).size(12).style(theme::Text::Color(color)))
+                            .push(output_button),
+                    );
+                    match (&row.state, self.expanded == Some(index)) {
+                        (TestState::Done(result), true) => column.push(
+                            Container::new(
+                                Text::new(format!("{}\n{}", result.exit_status, result.output))
+                                    .size(11)
+                                    .font(Font::with_name("DejaVu Sans Mono"))
+                                    .horizontal_alignment(alignment::Horizontal::Left),
+                            )
+                            .padding(5)
+                            .width(Length::Fill),
+                        ),
+                        _ => column,
+                    }
+                });
+
+        let mut run_all = Button::new(Text::new("Run all").size(14)).style(text_button());
+        let mut run_selected = Button::new(Text::new("Run selected").size(14)).style(text_button());
+        if self.queue.is_empty() {
+            run_all = run_all.on_press(TestsMessage::RunAll);
+            if rows.iter().any(|r| r.selected) {
+                run_selected = run_selected.on_press(TestsMessage::RunSelected);
+            }
+        }
+
+        Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Checkbox::new("Headless (requires xvfb-run)", self.headless)
+                            .on_toggle(TestsMessage::HeadlessToggled)
+                            .text_size(14),
+                    )
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(run_selected)
+                    .push(run_all),
+            )
+            .push(Scrollable::new(list).height(Length::Fill))
+            .into()
+    }
+}
+
+/// Asks the game which tests it knows about.
+/// `--tests` prints a tab-separated table of status and (quoted) name, preceded by a header.
+pub fn list_tests(executable: &Path) -> Result<Vec<GameTest>> {
+    let output = process::Command::new(executable)
+        .arg("--tests")
+        .output()
+        .with_context(|| "Failed to run the game")?;
+    if !output.status.success() {
+        bail!(
+            "The game exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (status, name) = line.split_once('\t')?;
+            let name = name.trim().trim_matches('"');
+            if name.is_empty() || (status == "status" && name == "name") {
+                return None;
+            }
+            Some(GameTest {
+                name: name.to_string(),
+                status: status.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Runs a single test with a throwaway config directory, so saves and preferences stay untouched.
+/// Headless runs wrap the game in `xvfb-run`, which is only available on Linux.
+pub fn run_test(executable: &Path, config_dir: &Path, name: &str, headless: bool) -> TestResult {
+    info!("Running test '{}'", name);
+    let started = Instant::now();
+    let mut cmd = if headless && cfg!(target_os = "linux") {
+        let mut cmd = process::Command::new("xvfb-run");
+        cmd.arg("--auto-servernum").arg(executable);
+        cmd
+    } else {
+        if headless {
+            warn!("Headless tests are only supported on Linux, running with a window");
+        }
+        process::Command::new(executable)
+    };
+    let output = fs::create_dir_all(config_dir).and_then(|()| {
+        cmd.arg("--config")
+            .arg(config_dir)
+            .arg("--test")
+            .arg(name)
+            .output()
+    });
+
+    let result = match output {
+        Ok(output) => TestResult {
+            name: name.to_string(),
+            passed: output.status.success(),
+            exit_status: output.status.to_string(),
+            output: format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+            duration: started.elapsed(),
+        },
+        Err(e) => TestResult {
+            name: name.to_string(),
+            passed: false,
+            exit_status: String::from("failed to start"),
+            output: e.to_string(),
+            duration: started.elapsed(),
+        },
+    };
+    if result.passed {
+        info!("Test '{}' passed", name);
+    } else {
+        warn!("Test '{}' failed with {}", name, result.exit_status);
+    }
+    result
+}
+
+fn escape_xml(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t' | '\r'))
+        .fold(String::with_capacity(s.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+pub fn to_junit_xml(suite: &str, results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|r| !r.passed).count();
+    let time: f32 = results.iter().map(|r| r.duration.as_secs_f32()).sum();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        escape_xml(suite),
+        results.len(),
+        failures,
+        time
+    );
+    for result in results {
+        xml.push_str(&format!(
+            "  <testcase classname=\"endless-sky\" name=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(&result.name),
+            result.duration.as_secs_f32()
+        ));
+        if !result.passed {
+            xml.push_str(&format!(
+                "    <failure message=\"{}\"/>\n",
+                escape_xml(&result.exit_status)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <system-out>{}</system-out>\n  </testcase>\n",
+            escape_xml(&result.output)
+        ));
+    }
+    xml.push_str("</testsuite>\n");
+    xml
+}
+
+const CLI_USAGE: &str = "Usage: eslauncher2 test <instance> [--list] [--headless] [--junit <file>] [test names...]
+On Windows the launcher has no console to print to, the results are written to ESLauncher2.log instead.
+Use --junit for a report file and the exit code for the overall outcome.";
+
+/// Prints a line of CLI output, or logs it where there is no console to print to.
+fn report(line: String) {
+    if cfg!(target_os = "windows") {
+        info!("{}", line);
+    } else {
+        println!("{line}");
+    }
+}
+
+/// Entry point for `eslauncher2 test ...`. Returns the process exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+    logger::init();
+    match cli(args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            error!("{:#}", e);
+            if cfg!(target_os = "windows") {
+                info!("{}", CLI_USAGE);
+            } else {
+                eprintln!("{CLI_USAGE}");
+            }
+            2
+        }
+    }
+}
+
+fn cli(args: &[String]) -> Result<bool> {
+    let mut args = args.iter();
+    let instance_name = args
+        .next()
+        .ok_or_else(|| anyhow!("Missing instance name"))?;
+    let mut junit = None;
+    let mut headless = false;
+    let mut list = false;
+    let mut names = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--junit" => junit = Some(args.next().ok_or_else(|| anyhow!("--junit needs a path"))?),
+            "--headless" => headless = true,
+            "--list" => list = true,
+            name => names.push(name.to_string()),
+        }
+    }
+
+    let instance = load_instances()?
+        .into_iter()
+        .find(|i| &i.name == instance_name)
+        .ok_or_else(|| anyhow!("No instance named '{}'", instance_name))?;
+    let tests = list_tests(&instance.executable)?;
+    if list {
+        for test in tests {
+            report(format!("{}\t{}", test.status, test.name));
+        }
+        return Ok(true);
+    }
+    for name in &names {
+        if !tests.iter().any(|t| &t.name == name) {
+            bail!("Unknown test '{}'", name);
+        }
+    }
+
+    let config_dir = instance.path.join("tests-config");
+    let results: Vec<TestResult> = tests
+        .iter()
+        .filter(|t| names.is_empty() || names.contains(&t.name))
+        .map(|t| run_test(&instance.executable, &config_dir, &t.name, headless))
+        .collect();
+    for result in &results {
+        report(format!(
+            "{}\t{:.1}s\t{}",
+            if result.passed { "PASS" } else { "FAIL" },
+            result.duration.as_secs_f32(),
+            result.name
+        ));
+    }
+    let passed = results.iter().filter(|r| r.passed).count();
+    report(format!("{}/{} tests passed", passed, results.len()));
+
+    if let Some(path) = junit {
+        fs::write(path, to_junit_xml(&instance.name, &results))
+            .with_context(|| format!("Failed to write {path}"))?;
+    }
+    Ok(passed == results.len())
+}
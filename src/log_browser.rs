@@ -0,0 +1,389 @@
+use crate::instance::Instance;
+use crate::style::{icon_button, log_container_for_level, text_button};
+use crate::{style, Message};
+use anyhow::Result;
+use iced::widget::{Button, Column, Container, Row, Scrollable, Space, Text, TextInput};
+use iced::{alignment, theme, Alignment, Color, Command, Element, Font, Length};
+use log::Level;
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Rendering tens of thousands of Text widgets grinds iced to a halt, so we cap the visible lines.
+const MAX_DISPLAYED_LINES: usize = 2000;
+
+/// Listing the sessions only looks for errors at the end of each file, where a crash shows up,
+/// so long logs don't have to be read in full.
+const MAX_SCANNED_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct LogBrowser {
+    pub instance_name: String,
+    log_dir: PathBuf,
+    /// Crash reports the instance's recorded sessions link to, which pruning keeps
+    crash_reports: Vec<PathBuf>,
+    sessions: Vec<LogSession>,
+    selected: Option<usize>,
+    lines: Vec<(Option<Level>, String)>,
+    /// Whether the selected session's files are still being read
+    loading: bool,
+    search: String,
+    retention_days: String,
+}
+
+/// The `.out` and `.err` files written by a single `play` call, identified by their common timestamp.
+#[derive(Debug, Clone)]
+pub struct LogSession {
+    time: String,
+    files: Vec<PathBuf>,
+    has_errors: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum LogBrowserMessage {
+    Loaded(Vec<LogSession>),
+    Refresh,
+    Select(usize),
+    LinesLoaded(usize, Vec<(Option<Level>, String)>),
+    SearchChanged(String),
+    RetentionChanged(String),
+    Prune,
+}
+
+impl LogBrowser {
+    pub fn new(instance: &Instance, retention_days: u32) -> (Self, Command<Message>) {
+        let log_dir = instance.path.join("logs");
+        (
+            Self {
+                instance_name: instance.name.clone(),
+                log_dir: log_dir.clone(),
+                crash_reports: instance
+                    .sessions
+                    .iter()
+                    .filter_map(|s| s.crash_report.clone())
+                    .collect(),
+                sessions: vec![],
+                selected: None,
+                lines: vec![],
+                loading: false,
+                search: String::new(),
+                retention_days: retention_days.to_string(),
+            },
+            Command::perform(load_sessions(log_dir), |sessions| {
+                Message::LogBrowserMessage(LogBrowserMessage::Loaded(sessions))
+            }),
+        )
+    }
+
+    pub fn retention_days(&self) -> Option<u32> {
+        self.retention_days.trim().parse().ok()
+    }
+
+    pub fn update(&mut self, message: LogBrowserMessage) -> Command<Message> {
+        match message {
+            LogBrowserMessage::Loaded(sessions) => {
+                self.sessions = sessions;
+                self.selected = None;
+                self.lines.clear();
+                self.loading = false;
+            }
+            LogBrowserMessage::Refresh => {
+                return Command::perform(load_sessions(self.log_dir.clone()), |sessions| {
+                    Message::LogBrowserMessage(LogBrowserMessage::Loaded(sessions))
+                });
+            }
+            LogBrowserMessage::Select(index) => {
+                if let Some(session) = self.sessions.get(index).cloned() {
+                    self.selected = Some(index);
+                    self.lines.clear();
+                    self.loading = true;
+                    return Command::perform(async move { read_lines(&session) }, move |lines| {
+                        Message::LogBrowserMessage(LogBrowserMessage::LinesLoaded(index, lines))
+                    });
+                }
+            }
+            LogBrowserMessage::LinesLoaded(index, lines) => {
+                // Another session may have been selected in the meantime
+                if self.selected == Some(index) {
+                    self.lines = lines;
+                    self.loading = false;
+                }
+            }
+            LogBrowserMessage::SearchChanged(search) => self.search = search,
+            LogBrowserMessage::RetentionChanged(days) => {
+                if days.chars().all(|c| c.is_ascii_digit()) {
+                    self.retention_days = days;
+                }
+            }
+            LogBrowserMessage::Prune => match self.retention_days() {
+                Some(days) => {
+                    let log_dir = self.log_dir.clone();
+                    let keep = self.crash_reports.clone();
+                    return Command::perform(prune(log_dir, days, keep), |sessions| {
+                        Message::LogBrowserMessage(LogBrowserMessage::Loaded(sessions))
+                    });
+                }
+                None => error!("Invalid log retention: '{}'", self.retention_days),
+            },
+        }
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<LogBrowserMessage> {
+        let session_list = self.sessions.iter().enumerate().fold(
+            Column::new().spacing(2),
+            |column, (index, session)| {
+                let mut label = Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(&session.time).size(14));
+                if session.has_errors {
+                    label = label.push(
+                        Text::new("errors")
+                            .size(12)
+                            .style(theme::Text::Color(Color::from_rgb(0.8, 0.2, 0.2))),
+                    );
+                }
+                column.push(
+                    Button::new(label)
+                        .style(if self.selected == Some(index) {
+                            text_button()
+                        } else {
+                            icon_button()
+                        })
+                        .width(Length::Fill)
+                        .on_press(LogBrowserMessage::Select(index)),
+                )
+            },
+        );
+
+        let search = self.search.to_lowercase();
+        let matching: Vec<_> = self
+            .lines
+            .iter()
+            .filter(|(_, line)| search.is_empty() || line.to_lowercase().contains(&search))
+            .collect();
+        // The end of the output is where a crash shows up
+        let hidden = matching.len().saturating_sub(MAX_DISPLAYED_LINES);
+        let log_view = matching[hidden..].iter().fold(
+            Column::new().spacing(1).align_items(Alignment::Start),
+            |column, (level, line)| {
+                column.push(
+                    Container::new(
+                        Text::new(line)
+                            .size(11)
+                            .font(Font::with_name("DejaVu Sans Mono"))
+                            .horizontal_alignment(alignment::Horizontal::Left),
+                    )
+                    .style(log_container_for_level(*level))
+                    .width(Length::Fill),
+                )
+            },
+        );
+
+        let log_view: Element<_> = if self.selected.is_none() {
+            Text::new("Select a session to view its logs")
+                .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6)))
+                .into()
+        } else if self.loading {
+            Text::new("Loading...")
+                .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6)))
+                .into()
+        } else if self.lines.is_empty() {
+            Text::new("This session didn't produce any output")
+                .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6)))
+                .into()
+        } else {
+            Column::new()
+                .spacing(5)
+                .push_maybe((hidden > 0).then(|| {
+                    Text::new(format!(
+                        "Showing the last {} of {} lines",
+                        MAX_DISPLAYED_LINES,
+                        matching.len()
+                    ))
+                    .size(12)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.)))
+                }))
+                .push(Scrollable::new(log_view).height(Length::Fill))
+                .into()
+        };
+
+        let mut prune_button = Button::new(Text::new("Prune").size(14)).style(text_button());
+        if self.retention_days().is_some() {
+            prune_button = prune_button.on_press(LogBrowserMessage::Prune);
+        }
+
+        Column::new()
+            .spacing(10)
+            .push(
+                TextInput::new("Search", &self.search)
+                    .on_input(LogBrowserMessage::SearchChanged)
+                    .padding(5)
+                    .size(14),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .height(Length::Fill)
+                    .push(
+                        Scrollable::new(if self.sessions.is_empty() {
+                            Column::new().push(
+                                Text::new("No logs yet")
+                                    .size(14)
+                                    .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+                            )
+                        } else {
+                            session_list
+                        })
+                        .width(Length::FillPortion(1)),
+                    )
+                    .push(Container::new(log_view).width(Length::FillPortion(3))),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Button::new(style::refresh_icon())
+                            .style(icon_button())
+                            .on_press(LogBrowserMessage::Refresh),
+                    )
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(Text::new("Delete logs older than").size(14))
+                    .push(
+                        TextInput::new("30", &self.retention_days)
+                            .on_input(LogBrowserMessage::RetentionChanged)
+                            .padding(5)
+                            .size(14)
+                            .width(Length::Fixed(50.)),
+                    )
+                    .push(Text::new("days").size(14))
+                    .push(prune_button),
+            )
+            .into()
+    }
+}
+
+/// Guesses the severity of a line of game output.
+fn classify(line: &str) -> Option<Level> {
+    let lower = line.to_lowercase();
+    if lower.contains("error") || lower.contains("fatal") || lower.contains("exception") {
+        Some(Level::Error)
+    } else if lower.contains("warn") {
+        Some(Level::Warn)
+    } else {
+        None
+    }
+}
+
+fn read_lines(session: &LogSession) -> Vec<(Option<Level>, String)> {
+    let mut lines = vec![];
+    for file in &session.files {
+        match fs::read(file) {
+            Ok(bytes) => {
+                let is_stderr = file.extension().and_then(OsStr::to_str) == Some("err");
+                lines.extend(String::from_utf8_lossy(&bytes).lines().map(|line| {
+                    let level = classify(line).or(if is_stderr { Some(Level::Warn) } else { None });
+                    (level, line.to_string())
+                }));
+            }
+            Err(e) => error!("Failed to read {}: {}", file.to_string_lossy(), e),
+        }
+    }
+    lines
+}
+
+/// Whether the last `MAX_SCANNED_BYTES` of the file contain an error, stopping at the first one.
+fn ends_with_errors(file: &Path) -> bool {
+    let Ok(mut f) = File::open(file) else {
+        return false;
+    };
+    let len = f.metadata().map(|m| m.len()).unwrap_or(0);
+    if f.seek(SeekFrom::Start(len.saturating_sub(MAX_SCANNED_BYTES)))
+        .is_err()
+    {
+        return false;
+    }
+    BufReader::new(f)
+        .split(b'\n')
+        .map_while(Result::ok)
+        .any(|line| classify(&String::from_utf8_lossy(&line)) == Some(Level::Error))
+}
+
+pub async fn load_sessions(log_dir: PathBuf) -> Vec<LogSession> {
+    scan_sessions(&log_dir).unwrap_or_else(|e| {
+        if log_dir.exists() {
+            error!("Failed to read {}: {:#}", log_dir.to_string_lossy(), e);
+        }
+        vec![]
+    })
+}
+
+fn scan_sessions(log_dir: &Path) -> Result<Vec<LogSession>> {
+    let mut sessions: Vec<LogSession> = vec![];
+    for entry in fs::read_dir(log_dir)? {
+        let path = entry?.path();
+        if !matches!(
+            path.extension().and_then(OsStr::to_str),
+            Some("out") | Some("err")
+        ) {
+            continue;
+        }
+        let Some(time) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        match sessions.iter_mut().find(|s| s.time == time) {
+            Some(session) => session.files.push(path),
+            None => sessions.push(LogSession {
+                time,
+                files: vec![path],
+                has_errors: false,
+            }),
+        }
+    }
+    for session in &mut sessions {
+        session.files.sort();
+        session.has_errors = log_dir.join(format!("{}-crash.zip", session.time)).exists()
+            || session.files.iter().any(|file| ends_with_errors(file));
+    }
+    // The timestamps sort chronologically, newest first is what you usually want
+    sessions.sort_by(|a, b| b.time.cmp(&a.time));
+    Ok(sessions)
+}
+
+/// Deletes all log files and crash reports older than `days` days, except for the crash reports in
+/// `keep` that sessions still link to, and returns the remaining sessions.
+pub async fn prune(log_dir: PathBuf, days: u32, keep: Vec<PathBuf>) -> Vec<LogSession> {
+    let max_age = Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+    let mut removed = 0;
+    match fs::read_dir(&log_dir) {
+        Ok(entries) => {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                let age = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok());
+                if path.is_file() && age.is_some_and(|age| age > max_age) && !keep.contains(&path) {
+                    match fs::remove_file(&path) {
+                        Ok(()) => removed += 1,
+                        Err(e) => error!("Failed to remove {}: {}", path.to_string_lossy(), e),
+                    }
+                }
+            }
+            info!(
+                "Removed {} log files older than {} days from {}",
+                removed,
+                days,
+                log_dir.to_string_lossy()
+            );
+        }
+        Err(e) => error!("Failed to read {}: {}", log_dir.to_string_lossy(), e),
+    }
+    load_sessions(log_dir).await
+}
@@ -22,27 +22,57 @@ use iced::{
 };
 use iced_aw::{TabLabel, Tabs};
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
+use crate::config_location::ConfigLocation;
+use crate::data_check::DataCheckMessage;
 use crate::install_frame::InstallFrameMessage;
 use crate::instance::{Instance, InstanceMessage, InstanceState, Progress};
+use crate::instance_panel::{InstancePanel, PanelType};
+use crate::instance_rename::{RenameMessage, RenameMode};
+use crate::integration_tests::TestsMessage;
+use crate::log_browser::LogBrowserMessage;
 use crate::music::{MusicCommand, MusicState};
-use crate::plugins_frame::PluginMessage;
+use crate::pilots_frame::PilotsMessage;
+use crate::plugin_profiles::PluginProfiles;
+use crate::plugins_frame::{
+    AnalysisMessage, AuthoringMessage, BulkMessage, CustomMessage, DetailMessage, ListMessage,
+    PluginMessage, ProfileMessage, SourceMessage,
+};
 use crate::settings::Settings;
 use crate::style::{icon_button, log_container, tab_bar};
+use crate::trash::{DeleteMessage, TrashMessage};
 
 mod archive;
+mod config_location;
+mod crash_report;
+mod data_check;
 mod github;
+mod icon_cache;
 mod install;
 mod install_frame;
 mod instance;
+mod instance_panel;
+mod instance_rename;
 mod instances_frame;
+mod integration_tests;
 mod jenkins;
+mod log_browser;
 mod logger;
 mod music;
+mod pilots_frame;
+mod plugin_analysis;
+mod plugin_authoring;
+mod plugin_details;
+mod plugin_profiles;
+mod plugins;
 mod plugins_frame;
+mod save_snapshots;
+mod saves;
 mod settings;
 mod style;
+mod trash;
 mod update;
 
 // Yes, this is terrible abuse of globals.
@@ -64,8 +94,15 @@ mod update;
 // - Doesn't randomly break after some messages
 // so here it will stay.
 static MESSAGE_QUEUE: Mutex<VecDeque<Message>> = Mutex::new(VecDeque::new());
+/// Nothing drains the message queue without a UI, e.g. when running the CLI
+static UI_RUNNING: AtomicBool = AtomicBool::new(false);
 
 pub fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "test") {
+        std::process::exit(integration_tests::run_cli(&args[1..]));
+    }
+    UI_RUNNING.store(true, Ordering::Relaxed);
     ESLauncher::run(iced::Settings::default())
 }
 
@@ -74,7 +111,9 @@ struct ESLauncher {
     music_sender: Sender<MusicCommand>,
     install_frame: install_frame::InstallFrame,
     instances_frame: instances_frame::InstancesFrame,
-    plugins_frame: plugins_frame::PluginsFrameState,
+    instance_panel: Option<InstancePanel>,
+    plugins_frame: plugins_frame::PluginsFrame,
+    pilots_frame: pilots_frame::PilotsFrame,
     message_receiver: MessageReceiver,
     log_buffer: Vec<String>,
     active_tab: Tab,
@@ -85,6 +124,7 @@ struct ESLauncher {
 pub enum Tab {
     Instances,
     Plugins,
+    Pilots,
 }
 
 #[derive(Debug, Clone)]
@@ -92,13 +132,35 @@ pub enum Message {
     InstallFrameMessage(InstallFrameMessage),
     InstanceMessage(String, InstanceMessage),
     PluginMessage(String, PluginMessage),
+    PluginLocationSelected(ConfigLocation),
+    PluginProfileMessage(ProfileMessage),
+    PluginBulkMessage(BulkMessage),
+    PluginListMessage(ListMessage),
+    PluginCustomMessage(CustomMessage),
+    PluginAnalysisMessage(AnalysisMessage),
+    PluginDetailMessage(DetailMessage),
+    PluginSourceMessage(SourceMessage),
+    PluginAuthoringMessage(AuthoringMessage),
+    PilotsMessage(PilotsMessage),
     AddInstance(Box<Instance>),
     RemoveInstance(Option<String>),
+    SaveInstances,
+    OpenPanel(String, PanelType),
+    LogBrowserMessage(LogBrowserMessage),
+    TestsMessage(TestsMessage),
+    DataCheckMessage(DataCheckMessage),
+    DeleteMessage(DeleteMessage),
+    RenameMessage(RenameMessage),
+    OpenTrash,
+    TrashMessage(TrashMessage),
+    ClosePanel,
     Dummy(()),
     FontLoaded(Result<(), font::Error>),
     MusicMessage(MusicCommand),
     TabSelected(Tab),
-    PluginFrameLoaded(Vec<plugins_frame::Plugin>),
+    InstanceSortChanged(instances_frame::InstanceSort),
+    PluginFrameLoaded(plugins_frame::Catalogue),
+    PluginIconLoaded(String, Vec<u8>),
     Log(String),
 }
 
@@ -121,17 +183,32 @@ impl Application for ESLauncher {
         }
 
         let settings = Settings::load();
+        let retention_days = settings.trash_retention_days;
+        thread::spawn(move || {
+            if let Err(e) = trash::purge_expired(retention_days) {
+                error!("Failed to empty the trash: {:#}", e);
+            }
+        });
         let music_sender = music::spawn(settings.music_state);
 
         check_for_update();
 
-        let (plugins_frame_state, plugins_frame_cmd) = plugins_frame::PluginsFrameState::new();
+        let (plugins_frame, plugins_frame_cmd) = plugins_frame::PluginsFrame::new(
+            settings.plugin_sort,
+            settings.plugin_index_sources.clone(),
+        );
+        let instances_frame = instances_frame::InstancesFrame {
+            sort: settings.instance_sort,
+            ..Default::default()
+        };
         (
             Self {
                 music_sender,
                 install_frame: install_frame::InstallFrame::default(),
-                instances_frame: instances_frame::InstancesFrame::default(),
-                plugins_frame: plugins_frame_state,
+                instances_frame,
+                instance_panel: None,
+                plugins_frame,
+                pilots_frame: pilots_frame::PilotsFrame::new(&settings),
                 message_receiver: MessageReceiver {},
                 log_buffer: vec![],
                 active_tab: Tab::Instances,
@@ -159,18 +236,67 @@ impl Application for ESLauncher {
             Message::InstanceMessage(name, msg) => {
                 match self.instances_frame.instances.get_mut(&name) {
                     None => error!("Failed to find internal Instance with name {}", &name),
-                    Some(instance) => return instance.update(msg),
+                    Some(instance) => return instance.update(msg, &self.settings),
                 }
             }
             Message::PluginMessage(name, msg) => {
-                if let plugins_frame::PluginsFrameState::Ready { plugins, .. } =
-                    &mut self.plugins_frame
-                {
-                    match plugins.iter_mut().find(|p| p.name == name) {
-                        None => error!("Failed to find internal Plug-In with name {}", name),
-                        Some(p) => return p.update(msg),
-                    }
+                return self.plugins_frame.update_plugin(
+                    &name,
+                    msg,
+                    &self.instances_frame.instances,
+                );
+            }
+            Message::PluginAnalysisMessage(msg) => {
+                return self
+                    .plugins_frame
+                    .update_analysis(msg, &self.instances_frame.instances);
+            }
+            Message::PluginDetailMessage(msg) => self.plugins_frame.update_details(msg),
+            Message::PluginSourceMessage(msg) => {
+                let cmd = self.plugins_frame.update_sources(msg);
+                if self.settings.plugin_index_sources != self.plugins_frame.index_sources {
+                    self.settings.plugin_index_sources = self.plugins_frame.index_sources.clone();
+                    if let Err(e) = self.settings.save() {
+                        error!("Failed to save settings.json: {:#?}", e);
+                    };
                 }
+                return cmd;
+            }
+            Message::PluginAuthoringMessage(msg) => {
+                return self
+                    .plugins_frame
+                    .update_authoring(msg, &self.instances_frame.instances);
+            }
+            Message::PluginCustomMessage(msg) => {
+                return self
+                    .plugins_frame
+                    .update_custom(msg, &self.instances_frame.instances);
+            }
+            Message::PluginListMessage(msg) => {
+                let cmd = self.plugins_frame.update_list(msg);
+                if self.settings.plugin_sort != self.plugins_frame.sort {
+                    self.settings.plugin_sort = self.plugins_frame.sort;
+                    if let Err(e) = self.settings.save() {
+                        error!("Failed to save settings.json: {:#?}", e);
+                    };
+                }
+                return cmd;
+            }
+            Message::PluginIconLoaded(name, icon) => self.plugins_frame.icon_loaded(&name, icon),
+            Message::PluginBulkMessage(msg) => {
+                return self
+                    .plugins_frame
+                    .update_bulk(msg, &self.instances_frame.instances);
+            }
+            Message::PluginProfileMessage(msg) => {
+                return self
+                    .plugins_frame
+                    .update_profiles(msg, &self.instances_frame.instances);
+            }
+            Message::PluginLocationSelected(location) => {
+                return self
+                    .plugins_frame
+                    .select_location(location, &self.instances_frame.instances);
             }
             Message::AddInstance(instance) => {
                 let is_ready = instance.state.is_ready();
@@ -183,10 +309,194 @@ impl Application for ESLauncher {
             }
             Message::RemoveInstance(option) => {
                 if let Some(name) = option {
+                    if self
+                        .instance_panel
+                        .as_ref()
+                        .is_some_and(|p| p.instance_name() == Some(name.as_str()))
+                    {
+                        self.instance_panel = None;
+                    }
                     self.instances_frame.instances.remove(&name);
                     instance::perform_save_instances(self.instances_frame.instances.clone());
                 }
             }
+            Message::SaveInstances => {
+                instance::perform_save_instances(self.instances_frame.instances.clone());
+            }
+            Message::OpenPanel(name, panel_type) => {
+                if let Some(instance) = self.instances_frame.instances.get(&name) {
+                    let (panel, cmd) = InstancePanel::open(panel_type, instance, &self.settings);
+                    self.instance_panel = Some(panel);
+                    return cmd;
+                }
+            }
+            Message::LogBrowserMessage(msg) => {
+                if let Some(InstancePanel::Logs(browser)) = &mut self.instance_panel {
+                    if let LogBrowserMessage::Prune = msg {
+                        if let Some(days) = browser.retention_days() {
+                            self.settings.log_retention_days = days;
+                            if let Err(e) = self.settings.save() {
+                                error!("Failed to save settings.json: {:#?}", e);
+                            };
+                        }
+                    }
+                    return browser.update(msg);
+                }
+            }
+            Message::TestsMessage(msg) => {
+                if let Some(InstancePanel::Tests(panel)) = &mut self.instance_panel {
+                    return panel.update(msg);
+                }
+            }
+            Message::DataCheckMessage(msg) => {
+                if let Some(InstancePanel::DataCheck(panel)) = &mut self.instance_panel {
+                    return panel.update(msg);
+                }
+            }
+            Message::DeleteMessage(msg) => {
+                if let Some(InstancePanel::ConfirmDelete(panel)) = &mut self.instance_panel {
+                    if let DeleteMessage::Confirm = msg {
+                        if let Some(instance) =
+                            self.instances_frame.instances.get(&panel.instance_name)
+                        {
+                            self.instance_panel = None;
+                            return Command::perform(
+                                instance::delete(instance.clone()),
+                                Message::RemoveInstance,
+                            );
+                        }
+                    }
+                    panel.update(msg);
+                }
+            }
+            Message::RenameMessage(msg) => {
+                if let Some(InstancePanel::Rename(panel)) = &mut self.instance_panel {
+                    match &msg {
+                        RenameMessage::Submit => {
+                            let Some(instance) =
+                                self.instances_frame.instances.get(&panel.instance_name)
+                            else {
+                                return Command::none();
+                            };
+                            let new_name = panel.new_name().to_string();
+                            let new_path = match instance_rename::check_name(
+                                &new_name,
+                                &self.instances_frame.instances,
+                            ) {
+                                Ok(path) => path,
+                                Err(e) => {
+                                    panel.reject(format!("{e:#}"));
+                                    return Command::none();
+                                }
+                            };
+                            let cmd = Command::perform(
+                                instance_rename::perform(
+                                    panel.mode,
+                                    instance.clone(),
+                                    new_name,
+                                    new_path,
+                                ),
+                                |result| Message::RenameMessage(RenameMessage::Done(result)),
+                            );
+                            panel.update(msg);
+                            return cmd;
+                        }
+                        RenameMessage::Done(Ok(instance)) => {
+                            let mut instances = self.instances_frame.instances.clone();
+                            let old = match panel.mode {
+                                RenameMode::Rename => instances.remove(&panel.instance_name),
+                                RenameMode::Clone => None,
+                            };
+                            instances.insert(instance.name.clone(), (**instance).clone());
+                            if let Err(e) = instance::save_instances(instances.clone()) {
+                                error!("Failed to save instances: {:#}", e);
+                                panel.update(RenameMessage::Done(Err(String::from(
+                                    "Failed to save instances.json",
+                                ))));
+                                // Keep the folder where instances.json expects it
+                                let new_path = instance.path.clone();
+                                let old_path = old.map(|old| old.path);
+                                return Command::perform(
+                                    async move {
+                                        let undone = match &old_path {
+                                            Some(old_path) => std::fs::rename(&new_path, old_path),
+                                            None => std::fs::remove_dir_all(&new_path),
+                                        };
+                                        if let Err(e) = undone {
+                                            error!(
+                                                "Failed to undo, {} is no longer listed: {}",
+                                                new_path.to_string_lossy(),
+                                                e
+                                            );
+                                        }
+                                    },
+                                    Message::Dummy,
+                                );
+                            }
+                            self.instances_frame.instances = instances;
+                            self.instance_panel = None;
+                            let Some(old) = old else {
+                                return Command::none();
+                            };
+                            // Everything that refers to the instance by its name or folder follows it
+                            let mut profiles = PluginProfiles::load();
+                            profiles.relocate(&old.path, &instance.path);
+                            if let Err(e) = profiles.save() {
+                                error!("Failed to save plugin-profiles.json: {:#}", e);
+                            }
+                            if let Err(e) = save_snapshots::relocate(&old.path, &instance.path) {
+                                error!("Failed to update the save snapshots: {:#}", e);
+                            }
+                            let instances = &self.instances_frame.instances;
+                            return Command::batch([
+                                self.plugins_frame.instance_renamed(
+                                    &old.name,
+                                    &instance.name,
+                                    instances,
+                                ),
+                                self.pilots_frame.instance_renamed(
+                                    &old.name,
+                                    &instance.name,
+                                    instances,
+                                ),
+                            ]);
+                        }
+                        _ => {}
+                    }
+                    panel.update(msg);
+                }
+            }
+            Message::OpenTrash => {
+                let (panel, cmd) = InstancePanel::open_trash(&self.settings);
+                self.instance_panel = Some(panel);
+                return cmd;
+            }
+            Message::TrashMessage(msg) => {
+                if let Some(InstancePanel::Trash(panel)) = &mut self.instance_panel {
+                    if let TrashMessage::Restore(id) = &msg {
+                        let instance = panel.entry(id).and_then(|e| e.instance.as_ref());
+                        if let Some(instance) = instance
+                            .filter(|i| self.instances_frame.instances.contains_key(&i.name))
+                        {
+                            error!(
+                                "Can't restore {}, there already is an instance with that name",
+                                instance.name
+                            );
+                            return Command::none();
+                        }
+                    }
+                    if let TrashMessage::Prune = msg {
+                        if let Some(days) = panel.retention_days() {
+                            self.settings.trash_retention_days = days;
+                            if let Err(e) = self.settings.save() {
+                                error!("Failed to save settings.json: {:#?}", e);
+                            };
+                        }
+                    }
+                    return panel.update(msg);
+                }
+            }
+            Message::ClosePanel => self.instance_panel = None,
             Message::MusicMessage(cmd) => {
                 self.music_sender.send(cmd).ok();
                 self.settings.music_state = match cmd {
@@ -198,9 +508,41 @@ impl Application for ESLauncher {
                     error!("Failed to save settings.json: {:#?}", e);
                 };
             }
-            Message::TabSelected(active_tab) => self.active_tab = active_tab,
-            Message::PluginFrameLoaded(plugins) => {
-                self.plugins_frame = plugins_frame::PluginsFrameState::from(plugins);
+            Message::PilotsMessage(msg) => {
+                if let PilotsMessage::SaveSnapshotCount = msg {
+                    if let Some(count) = self.pilots_frame.snapshot_count() {
+                        self.settings.save_snapshot_count = count;
+                        if let Err(e) = self.settings.save() {
+                            error!("Failed to save settings.json: {:#?}", e);
+                        };
+                    }
+                }
+                return self
+                    .pilots_frame
+                    .update(msg, &self.instances_frame.instances);
+            }
+            Message::TabSelected(active_tab) => {
+                self.active_tab = active_tab;
+                match self.active_tab {
+                    Tab::Plugins => self
+                        .plugins_frame
+                        .refresh_installed(&self.instances_frame.instances),
+                    Tab::Pilots => {
+                        return self.pilots_frame.refresh(&self.instances_frame.instances);
+                    }
+                    Tab::Instances => {}
+                }
+            }
+            Message::InstanceSortChanged(sort) => {
+                self.instances_frame.sort = sort;
+                self.settings.instance_sort = sort;
+                if let Err(e) = self.settings.save() {
+                    error!("Failed to save settings.json: {:#?}", e);
+                };
+            }
+            Message::PluginFrameLoaded(catalogue) => {
+                self.plugins_frame
+                    .loaded(catalogue, &self.instances_frame.instances);
             }
             Message::Log(line) => self.log_buffer.push(line),
             Message::Dummy(()) => (),
@@ -233,7 +575,10 @@ impl Application for ESLauncher {
                     Row::new()
                         .push(self.instances_frame.view())
                         .push(iced::widget::vertical_rule(2))
-                        .push(self.install_frame.view().map(Message::InstallFrameMessage))
+                        .push(match &self.instance_panel {
+                            Some(panel) => panel.view(),
+                            None => self.install_frame.view().map(Message::InstallFrameMessage),
+                        })
                         .spacing(10)
                         .padding(iced::Padding {
                             top: 0.0,
@@ -247,10 +592,25 @@ impl Application for ESLauncher {
             )
             .push(
                 Tab::Plugins,
-                TabLabel::Text("Plugins".into()),
+                TabLabel::Text(match self.plugins_frame.updates_available() {
+                    0 => String::from("Plugins"),
+                    updates => format!("Plugins ({updates} updates)"),
+                }),
                 iced::widget::column([
                     iced::widget::horizontal_rule(2).into(),
-                    self.plugins_frame.view().into(),
+                    self.plugins_frame
+                        .view(&self.instances_frame.instances)
+                        .into(),
+                ]),
+            )
+            .push(
+                Tab::Pilots,
+                TabLabel::Text("Pilots".into()),
+                iced::widget::column([
+                    iced::widget::horizontal_rule(2).into(),
+                    self.pilots_frame
+                        .view(&self.instances_frame.instances)
+                        .map(Message::PilotsMessage),
                 ]),
             )
             .set_active_tab(&self.active_tab)
@@ -384,6 +744,9 @@ impl iced::advanced::subscription::Recipe for MessageReceiver {
 }
 
 pub fn send_message(message: Message) {
+    if !UI_RUNNING.load(Ordering::Relaxed) {
+        return;
+    }
     match crate::MESSAGE_QUEUE.lock() {
         Ok(mut queue) => queue.push_back(message),
         Err(e) => {
@@ -0,0 +1,753 @@
+use crate::config_location::ConfigLocation;
+use crate::github;
+use crate::instance::{format_timestamp, Instance};
+use crate::save_snapshots::{self, Snapshot};
+use crate::saves::{self, Pilot};
+use crate::settings::Settings;
+use crate::style::{hint_text, icon_button, text_button};
+use crate::{style, Message};
+use anyhow::Result;
+use iced::widget::{
+    Button, Checkbox, Column, Container, PickList, Row, Scrollable, Space, Text, TextInput,
+};
+use iced::{alignment, theme, Alignment, Color, Command, Element, Length};
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+lazy_static! {
+    /// Whether one build is older than another, by version pair. Builds don't change,
+    /// so GitHub only needs to be asked once per pair.
+    static ref COMPARISONS: Mutex<BTreeMap<(String, String), bool>> = Mutex::default();
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PilotsFrame {
+    location: ConfigLocation,
+    pilots: Vec<Pilot>,
+    renaming: Option<(usize, String)>,
+    /// The pilot whose deletion awaits confirmation
+    confirm_delete: Option<usize>,
+    /// Snapshots of the current location, `Some` while the snapshot list is shown
+    snapshots: Option<Vec<Snapshot>>,
+    expanded_snapshot: Option<usize>,
+    snapshot_count: String,
+    /// The snapshot count in the settings, which the input above only changes once saved
+    saved_snapshot_count: usize,
+    /// IDs of the pilots selected for copying or moving
+    selected: BTreeSet<String>,
+    transfer_target: Option<ConfigLocation>,
+    transfer_warning: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PilotsMessage {
+    LocationSelected(ConfigLocation),
+    Refresh,
+    Loaded(Vec<Pilot>),
+    Open(usize),
+    Duplicate(usize),
+    StartRename(usize),
+    RenameInput(String),
+    ConfirmRename,
+    CancelRename,
+    /// Asks for confirmation first
+    Delete(usize),
+    ConfirmDelete,
+    CancelDelete,
+    ToggleSnapshots,
+    SnapshotsLoaded(Vec<Snapshot>),
+    ExpandSnapshot(usize),
+    RestorePilot(usize, String),
+    SnapshotCountChanged(String),
+    SaveSnapshotCount,
+    ToggleSelected(String, bool),
+    ClearSelection,
+    TransferTargetSelected(ConfigLocation),
+    TransferChecked(ConfigLocation, Option<String>),
+    Transfer {
+        move_pilots: bool,
+    },
+}
+
+impl PilotsFrame {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            snapshot_count: settings.save_snapshot_count.to_string(),
+            saved_snapshot_count: settings.save_snapshot_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn snapshot_count(&self) -> Option<usize> {
+        self.snapshot_count.trim().parse().ok()
+    }
+
+    pub fn update(
+        &mut self,
+        message: PilotsMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        match message {
+            PilotsMessage::LocationSelected(location) => {
+                self.location = location;
+                self.clear_selection();
+                return self.refresh(instances);
+            }
+            PilotsMessage::Refresh => return self.refresh(instances),
+            PilotsMessage::Loaded(pilots) => {
+                self.pilots = pilots;
+                self.renaming = None;
+                self.confirm_delete = None;
+                let pilots = &self.pilots;
+                self.selected
+                    .retain(|id| pilots.iter().any(|p| &p.id == id));
+            }
+            PilotsMessage::Open(index) => {
+                if let Some(pilot) = self.pilots.get(index) {
+                    if let Err(e) = open::that(&pilot.file) {
+                        error!("Failed to open {}: {}", pilot.file.to_string_lossy(), e);
+                    }
+                }
+            }
+            PilotsMessage::Duplicate(index) => {
+                if let Some(pilot) = self.pilots.get(index) {
+                    if let Err(e) = saves::duplicate_pilot(pilot) {
+                        error!("Failed to duplicate pilot {}: {:#}", pilot.id, e);
+                    }
+                    return self.refresh(instances);
+                }
+            }
+            PilotsMessage::StartRename(index) => {
+                if let Some(pilot) = self.pilots.get(index) {
+                    self.renaming = Some((index, pilot.id.clone()));
+                }
+            }
+            PilotsMessage::RenameInput(name) => {
+                if let Some((_, new_name)) = &mut self.renaming {
+                    *new_name = name;
+                }
+            }
+            PilotsMessage::ConfirmRename => {
+                if let Some((index, new_name)) = self.renaming.take() {
+                    if let Some(pilot) = self.pilots.get(index) {
+                        if let Err(e) = saves::rename_pilot(pilot, &new_name) {
+                            error!("Failed to rename pilot {}: {:#}", pilot.id, e);
+                        }
+                    }
+                    return self.refresh(instances);
+                }
+            }
+            PilotsMessage::CancelRename => self.renaming = None,
+            PilotsMessage::Delete(index) => self.confirm_delete = Some(index),
+            PilotsMessage::ConfirmDelete => {
+                if let Some(pilot) = self.confirm_delete.take().and_then(|i| self.pilots.get(i)) {
+                    if let Err(e) = saves::delete_pilot(pilot) {
+                        error!("Failed to delete pilot {}: {:#}", pilot.id, e);
+                    }
+                    return self.refresh(instances);
+                }
+            }
+            PilotsMessage::CancelDelete => self.confirm_delete = None,
+            PilotsMessage::ToggleSnapshots => {
+                if self.snapshots.take().is_none() {
+                    self.snapshots = Some(vec![]);
+                    return self.refresh(instances);
+                }
+            }
+            PilotsMessage::SnapshotsLoaded(snapshots) => {
+                if self.snapshots.is_some() {
+                    let saves_dir = self.location.saves_dir(instances);
+                    self.snapshots = Some(
+                        snapshots
+                            .into_iter()
+                            .filter(|s| Some(&s.saves_dir) == saves_dir.as_ref())
+                            .collect(),
+                    );
+                    self.expanded_snapshot = None;
+                }
+            }
+            PilotsMessage::ExpandSnapshot(index) => {
+                self.expanded_snapshot = if self.expanded_snapshot == Some(index) {
+                    None
+                } else {
+                    Some(index)
+                };
+            }
+            PilotsMessage::RestorePilot(index, pilot) => {
+                let snapshot = self.snapshots.as_ref().and_then(|s| s.get(index));
+                if let (Some(snapshot), Some(saves_dir)) =
+                    (snapshot, self.location.saves_dir(instances))
+                {
+                    if let Err(e) = save_snapshots::restore_pilot(
+                        snapshot,
+                        &pilot,
+                        &saves_dir,
+                        self.saved_snapshot_count,
+                    ) {
+                        error!("Failed to restore pilot {}: {:#}", pilot, e);
+                    }
+                    return self.refresh(instances);
+                }
+            }
+            PilotsMessage::SnapshotCountChanged(count) => self.snapshot_count = count,
+            // Persisted by the caller
+            PilotsMessage::SaveSnapshotCount => {
+                if let Some(count) = self.snapshot_count() {
+                    self.saved_snapshot_count = count;
+                }
+            }
+            PilotsMessage::ToggleSelected(id, selected) => {
+                if selected {
+                    self.selected.insert(id);
+                } else {
+                    self.selected.remove(&id);
+                }
+                if self.selected.is_empty() {
+                    self.clear_selection();
+                } else {
+                    return self.check_transfer(instances);
+                }
+            }
+            PilotsMessage::ClearSelection => self.clear_selection(),
+            PilotsMessage::TransferTargetSelected(target) => {
+                self.transfer_target = Some(target);
+                return self.check_transfer(instances);
+            }
+            PilotsMessage::TransferChecked(target, warning) => {
+                // Ignore results for a target that is no longer selected
+                if self.transfer_target.as_ref() == Some(&target) {
+                    self.transfer_warning = warning;
+                }
+            }
+            PilotsMessage::Transfer { move_pilots } => {
+                let Some(target) = &self.transfer_target else {
+                    return Command::none();
+                };
+                let Some(target_dir) = target.saves_dir(instances) else {
+                    error!("Failed to get saves directory for {}", target);
+                    return Command::none();
+                };
+                for pilot in self.pilots.iter().filter(|p| self.selected.contains(&p.id)) {
+                    let result = saves::copy_pilot(pilot, &target_dir, None).and_then(|()| {
+                        if move_pilots {
+                            saves::delete_pilot(pilot)
+                        } else {
+                            Ok(())
+                        }
+                    });
+                    match result {
+                        Ok(()) => info!(
+                            "{} pilot {} to {}",
+                            if move_pilots { "Moved" } else { "Copied" },
+                            pilot.id,
+                            target
+                        ),
+                        Err(e) => error!("Failed to transfer pilot {}: {:#}", pilot.id, e),
+                    }
+                }
+                self.clear_selection();
+                return self.refresh(instances);
+            }
+        }
+        Command::none()
+    }
+
+    /// Keeps showing a renamed instance's saves, whose files have moved.
+    pub fn instance_renamed(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        if let Some(target) = &mut self.transfer_target {
+            target.rename_instance(old_name, new_name);
+        }
+        if self.location.rename_instance(old_name, new_name) {
+            self.snapshots = None;
+            self.expanded_snapshot = None;
+            return self.refresh(instances);
+        }
+        Command::none()
+    }
+
+    fn clear_selection(&mut self) {
+        self.selected.clear();
+        self.transfer_target = None;
+        self.transfer_warning = None;
+    }
+
+    /// Checks whether the selected pilots were last written by a newer build than the target's.
+    fn check_transfer(&mut self, instances: &BTreeMap<String, Instance>) -> Command<Message> {
+        self.transfer_warning = None;
+        let Some(target) = self.transfer_target.clone() else {
+            return Command::none();
+        };
+        let Some(target_version) = target.version(instances).map(String::from) else {
+            return Command::none();
+        };
+        let source_versions: BTreeSet<String> = self
+            .pilots
+            .iter()
+            .filter(|p| self.selected.contains(&p.id))
+            .filter_map(|p| last_writer(&self.location, &p.file, instances))
+            .map(|i| i.version.clone())
+            .collect();
+        Command::perform(
+            {
+                let target = target.clone();
+                async move {
+                    let newer: Vec<String> = source_versions
+                        .into_iter()
+                        .filter(|version| {
+                            is_older(&target_version, version).unwrap_or_else(|e| {
+                                warn!(
+                                    "Failed to compare {} with {}: {:#}",
+                                    target_version, version, e
+                                );
+                                false
+                            })
+                        })
+                        .collect();
+                    if newer.is_empty() {
+                        return None;
+                    }
+                    Some(format!(
+                        "{target} runs {target_version}, which is older than {} that last wrote \
+                        these saves. It might not be able to load them correctly.",
+                        newer.join(", ")
+                    ))
+                }
+            },
+            move |warning| Message::PilotsMessage(PilotsMessage::TransferChecked(target, warning)),
+        )
+    }
+
+    pub fn refresh(&mut self, instances: &BTreeMap<String, Instance>) -> Command<Message> {
+        let Some(saves_dir) = self.location.saves_dir(instances) else {
+            error!("Failed to get saves directory for {}", self.location);
+            self.location = ConfigLocation::Shared;
+            return Command::none();
+        };
+        let load_pilots = Command::perform(
+            async move {
+                if !saves_dir.exists() {
+                    return vec![];
+                }
+                saves::load_pilots(&saves_dir).unwrap_or_else(|e| {
+                    error!("Failed to load pilots: {:#}", e);
+                    vec![]
+                })
+            },
+            |pilots| Message::PilotsMessage(PilotsMessage::Loaded(pilots)),
+        );
+        if self.snapshots.is_none() {
+            return load_pilots;
+        }
+        Command::batch(vec![
+            load_pilots,
+            Command::perform(
+                async {
+                    save_snapshots::list().unwrap_or_else(|e| {
+                        error!("Failed to load save snapshots: {:#}", e);
+                        vec![]
+                    })
+                },
+                |snapshots| Message::PilotsMessage(PilotsMessage::SnapshotsLoaded(snapshots)),
+            ),
+        ])
+    }
+
+    pub fn view(&self, instances: &BTreeMap<String, Instance>) -> Element<PilotsMessage> {
+        let pilot_list: Element<_> = if let Some(snapshots) = &self.snapshots {
+            self.snapshots_view(snapshots)
+        } else if self.pilots.is_empty() {
+            hint_text("No pilots found")
+                .horizontal_alignment(alignment::Horizontal::Center)
+                .width(Length::Fill)
+                .into()
+        } else {
+            self.pilots
+                .iter()
+                .enumerate()
+                .fold(
+                    Column::new().padding(20).spacing(5).width(Length::Fill),
+                    |column, (index, pilot)| {
+                        column
+                            .push(iced::widget::horizontal_rule(2))
+                            .push(self.pilot_view(index, pilot))
+                    },
+                )
+                .into()
+        };
+
+        Container::new(
+            Column::new()
+                .spacing(10)
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(Text::new("Saves").size(14))
+                        .push(
+                            PickList::new(
+                                ConfigLocation::all(instances),
+                                Some(self.location.clone()),
+                                PilotsMessage::LocationSelected,
+                            )
+                            .text_size(14),
+                        )
+                        .push(Space::new(Length::Fill, Length::Shrink))
+                        .push(
+                            Button::new(
+                                Text::new(if self.snapshots.is_some() {
+                                    "Pilots"
+                                } else {
+                                    "Snapshots"
+                                })
+                                .size(14),
+                            )
+                            .style(text_button())
+                            .on_press(PilotsMessage::ToggleSnapshots),
+                        )
+                        .push(
+                            Button::new(style::refresh_icon())
+                                .style(icon_button())
+                                .on_press(PilotsMessage::Refresh),
+                        ),
+                )
+                .push_maybe(if self.snapshots.is_none() && !self.selected.is_empty() {
+                    Some(self.transfer_view(instances))
+                } else {
+                    None
+                })
+                .push(Scrollable::new(pilot_list)),
+        )
+        .width(Length::Fill)
+        .padding(iced::Padding {
+            top: 10.0,
+            right: 30.0,
+            bottom: 0.0,
+            left: 30.0,
+        })
+        .into()
+    }
+
+    fn transfer_view(&self, instances: &BTreeMap<String, Instance>) -> Element<PilotsMessage> {
+        let targets: Vec<ConfigLocation> = ConfigLocation::all(instances)
+            .into_iter()
+            .filter(|l| l != &self.location)
+            .collect();
+        let button = |label, move_pilots| {
+            let mut button = Button::new(Text::new(label).size(14)).style(text_button());
+            if self.transfer_target.is_some() {
+                button = button.on_press(PilotsMessage::Transfer { move_pilots });
+            }
+            button
+        };
+
+        Column::new()
+            .spacing(5)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(format!("{} selected, to", self.selected.len())).size(14))
+                    .push(
+                        PickList::new(
+                            targets,
+                            self.transfer_target.clone(),
+                            PilotsMessage::TransferTargetSelected,
+                        )
+                        .placeholder("Choose saves")
+                        .text_size(14),
+                    )
+                    .push(button("Copy", false))
+                    .push(button("Move", true))
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(
+                        Button::new(style::close_icon())
+                            .style(icon_button())
+                            .on_press(PilotsMessage::ClearSelection),
+                    ),
+            )
+            .push_maybe(self.transfer_warning.as_ref().map(|warning| {
+                Text::new(warning)
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.)))
+            }))
+            .into()
+    }
+
+    fn snapshots_view<'a>(&'a self, snapshots: &'a [Snapshot]) -> Element<'a, PilotsMessage> {
+        let mut save_button = Button::new(Text::new("Save").size(14)).style(text_button());
+        if self.snapshot_count().is_some() {
+            save_button = save_button.on_press(PilotsMessage::SaveSnapshotCount);
+        }
+        let settings_row = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(hint_text(
+                "The saves are snapshotted every time an instance is launched.",
+            ))
+            .push(Space::new(Length::Fill, Length::Shrink))
+            .push(Text::new("Keep").size(14))
+            .push(
+                TextInput::new("20", &self.snapshot_count)
+                    .on_input(PilotsMessage::SnapshotCountChanged)
+                    .on_submit(PilotsMessage::SaveSnapshotCount)
+                    .padding(5)
+                    .size(14)
+                    .width(Length::Fixed(50.)),
+            )
+            .push(Text::new("snapshots").size(14))
+            .push(save_button);
+
+        let column = Column::new()
+            .padding(20)
+            .spacing(5)
+            .width(Length::Fill)
+            .push(settings_row);
+        if snapshots.is_empty() {
+            return column
+                .push(iced::widget::horizontal_rule(2))
+                .push(hint_text("No snapshots of these saves yet"))
+                .into();
+        }
+        snapshots
+            .iter()
+            .enumerate()
+            .fold(column, |column, (index, snapshot)| {
+                let pilots = snapshot.pilots();
+                let mut column = column.push(iced::widget::horizontal_rule(2)).push(
+                    Button::new(
+                        Column::new()
+                            .push(Text::new(format_timestamp(snapshot.created)))
+                            .push(
+                                Text::new(format!(
+                                    "{} ({}) · {} pilot{}",
+                                    snapshot.instance,
+                                    snapshot.version,
+                                    pilots.len(),
+                                    if pilots.len() == 1 { "" } else { "s" },
+                                ))
+                                .size(14)
+                                .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+                            ),
+                    )
+                    .style(text_button())
+                    .width(Length::Fill)
+                    .on_press(PilotsMessage::ExpandSnapshot(index)),
+                );
+                if self.expanded_snapshot == Some(index) {
+                    for pilot in pilots {
+                        column = column.push(
+                            Row::new()
+                                .spacing(10)
+                                .padding(iced::Padding {
+                                    top: 0.0,
+                                    right: 10.0,
+                                    bottom: 0.0,
+                                    left: 25.0,
+                                })
+                                .align_items(Alignment::Center)
+                                .push(Text::new(pilot).size(14).width(Length::Fill))
+                                .push(
+                                    Button::new(Text::new("Restore").size(14))
+                                        .style(text_button())
+                                        .on_press(PilotsMessage::RestorePilot(
+                                            index,
+                                            pilot.to_string(),
+                                        )),
+                                ),
+                        );
+                    }
+                }
+                column
+            })
+            .into()
+    }
+
+    fn pilot_view<'a>(&'a self, index: usize, pilot: &'a Pilot) -> Element<'a, PilotsMessage> {
+        let info = &pilot.info;
+        let details = format!(
+            "{} · {} · {} credits · {} ship{}",
+            info.date.as_deref().unwrap_or("unknown date"),
+            info.system.as_deref().unwrap_or("unknown system"),
+            info.credits
+                .map_or_else(|| String::from("?"), format_credits),
+            info.ships,
+            if info.ships == 1 { "" } else { "s" },
+        );
+
+        let title: Element<_> = match &self.renaming {
+            Some((renaming, new_name)) if *renaming == index => Row::new()
+                .spacing(5)
+                .push(
+                    TextInput::new("New name", new_name)
+                        .on_input(PilotsMessage::RenameInput)
+                        .on_submit(PilotsMessage::ConfirmRename)
+                        .padding(5),
+                )
+                .push(
+                    Button::new(Text::new("Rename").size(14))
+                        .style(text_button())
+                        .on_press(PilotsMessage::ConfirmRename),
+                )
+                .push(
+                    Button::new(Text::new("Cancel").size(14))
+                        .style(text_button())
+                        .on_press(PilotsMessage::CancelRename),
+                )
+                .into(),
+            _ => Text::new(info.name.as_deref().unwrap_or(&pilot.id)).into(),
+        };
+
+        let id = pilot.id.clone();
+        let row = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                Checkbox::new("", self.selected.contains(&pilot.id))
+                    .on_toggle(move |selected| PilotsMessage::ToggleSelected(id.clone(), selected)),
+            )
+            .push(
+                Column::new()
+                    .width(Length::Fill)
+                    .push(title)
+                    .push(
+                        Text::new(details)
+                            .size(14)
+                            .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+                    )
+                    .push(
+                        Text::new(format!("{} backups", pilot.backups.len()))
+                            .size(12)
+                            .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+                    ),
+            )
+            .push(
+                Button::new(style::folder_icon())
+                    .style(icon_button())
+                    .on_press(PilotsMessage::Open(index)),
+            )
+            .push(
+                Button::new(style::copy_icon())
+                    .style(icon_button())
+                    .on_press(PilotsMessage::Duplicate(index)),
+            )
+            .push(
+                Button::new(style::rename_icon())
+                    .style(icon_button())
+                    .on_press(PilotsMessage::StartRename(index)),
+            )
+            .push(
+                Button::new(style::delete_icon())
+                    .style(theme::Button::Destructive)
+                    .on_press(PilotsMessage::Delete(index)),
+            );
+        let confirmation = (self.confirm_delete == Some(index)).then(|| {
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Text::new(format!(
+                        "Move {} and its {} backups to the trash?",
+                        info.name.as_deref().unwrap_or(&pilot.id),
+                        pilot.backups.len()
+                    ))
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.)))
+                    .width(Length::Fill),
+                )
+                .push(
+                    Button::new(Text::new("Delete").size(14))
+                        .style(theme::Button::Destructive)
+                        .on_press(PilotsMessage::ConfirmDelete),
+                )
+                .push(
+                    Button::new(Text::new("Cancel").size(14))
+                        .style(text_button())
+                        .on_press(PilotsMessage::CancelDelete),
+                )
+        });
+        Column::new()
+            .spacing(5)
+            .padding(10)
+            .push(row)
+            .push_maybe(confirmation)
+            .into()
+    }
+}
+
+fn format_credits(credits: i64) -> String {
+    let digits = credits.unsigned_abs().to_string();
+    let mut grouped = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<String>>()
+        .join(",");
+    if credits < 0 {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
+
+/// The instance that last wrote `file`, judged by which session its modification time falls into.
+fn last_writer<'a>(
+    location: &ConfigLocation,
+    file: &Path,
+    instances: &'a BTreeMap<String, Instance>,
+) -> Option<&'a Instance> {
+    let modified = fs::metadata(file)
+        .and_then(|m| m.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs() as i64;
+    let instances: Vec<&Instance> = location.instances(instances).collect();
+    instances
+        .iter()
+        .find(|i| {
+            i.sessions
+                .iter()
+                .any(|s| s.start <= modified && modified <= s.end + 60)
+        })
+        .or_else(|| instances.iter().max_by_key(|i| i.last_played()))
+        .copied()
+}
+
+/// Whether build `version` is older than `than`.
+/// Release tags are compared locally, anything else (commit SHAs of continuous builds and PRs) is left to GitHub.
+/// Builds that have diverged, e.g. two PRs, aren't older than one another.
+fn is_older(version: &str, than: &str) -> Result<bool> {
+    if version == than {
+        return Ok(false);
+    }
+    if let (Some(version), Some(than)) = (release_version(version), release_version(than)) {
+        return Ok(version < than);
+    }
+    let key = (version.to_string(), than.to_string());
+    if let Some(older) = COMPARISONS.lock().unwrap().get(&key) {
+        return Ok(*older);
+    }
+    let older = github::compare(than, version)?.status == "behind";
+    COMPARISONS.lock().unwrap().insert(key, older);
+    Ok(older)
+}
+
+fn release_version(version: &str) -> Option<Vec<u32>> {
+    if !version.contains('.') {
+        return None;
+    }
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
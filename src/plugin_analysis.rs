@@ -0,0 +1,214 @@
+use crate::plugins::InstalledPlugin;
+use crate::saves::tokenize;
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Top-level data nodes whose definitions replace each other when several plugins provide them.
+const OBJECT_KINDS: [&str; 5] = ["ship", "outfit", "system", "government", "mission"];
+
+/// A data object, as `(kind, name)`.
+pub type Object = (String, String);
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub plugins: (String, String),
+    pub objects: Vec<Object>,
+}
+
+/// What a plugin declares about other plugins in its `plugin.txt`.
+#[derive(Debug, Clone, Default)]
+pub struct PluginManifest {
+    pub name: Option<String>,
+    pub game_version: Option<String>,
+    pub requires: Vec<String>,
+    pub optional: Vec<String>,
+    pub conflicts: Vec<String>,
+}
+
+impl PluginManifest {
+    pub fn declares_dependencies(&self) -> bool {
+        self.game_version.is_some()
+            || !self.requires.is_empty()
+            || !self.optional.is_empty()
+            || !self.conflicts.is_empty()
+    }
+
+    /// E.g. `requires A, B · optional C · game version 0.10.8`
+    pub fn describe_dependencies(&self) -> String {
+        [
+            ("requires", self.requires.join(", ")),
+            ("optional", self.optional.join(", ")),
+            ("conflicts with", self.conflicts.join(", ")),
+            (
+                "game version",
+                self.game_version.clone().unwrap_or_default(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, list)| !list.is_empty())
+        .map(|(label, list)| format!("{label} {list}"))
+        .collect::<Vec<String>>()
+        .join(" · ")
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PluginAnalysis {
+    /// Objects defined by more than one plugin, per pair of plugins
+    pub conflicts: Vec<Conflict>,
+    /// Missing requirements and declared conflicts between enabled plugins
+    pub warnings: Vec<String>,
+    /// The dependencies of each plugin that declares any
+    pub dependencies: Vec<(String, PluginManifest)>,
+}
+
+/// Analyses the enabled plugins among `installed`.
+pub fn analyse(installed: &[InstalledPlugin]) -> Result<PluginAnalysis> {
+    let enabled: Vec<&InstalledPlugin> = installed.iter().filter(|p| p.enabled).collect();
+    let mut analysis = PluginAnalysis::default();
+
+    let mut definitions: BTreeMap<Object, Vec<&str>> = BTreeMap::new();
+    for plugin in &enabled {
+        for object in definitions_of(&plugin.path)? {
+            definitions.entry(object).or_default().push(&plugin.name);
+        }
+    }
+    let mut pairs: BTreeMap<(String, String), Vec<Object>> = BTreeMap::new();
+    for (object, plugins) in definitions {
+        for (i, a) in plugins.iter().enumerate() {
+            for b in &plugins[i + 1..] {
+                pairs
+                    .entry((a.to_string(), b.to_string()))
+                    .or_default()
+                    .push(object.clone());
+            }
+        }
+    }
+    analysis.conflicts = pairs
+        .into_iter()
+        .map(|(plugins, objects)| Conflict { plugins, objects })
+        .collect();
+
+    // Plugins refer to each other by the name in their plugin.txt, which may differ from the folder name
+    let manifests: Vec<(&InstalledPlugin, PluginManifest)> = enabled
+        .iter()
+        .map(|p| (*p, read_manifest(&p.path)))
+        .collect();
+    let enabled_names: BTreeSet<&str> = manifests
+        .iter()
+        .flat_map(|(p, m)| [Some(p.name.as_str()), m.name.as_deref()])
+        .flatten()
+        .collect();
+    for (plugin, manifest) in &manifests {
+        for required in &manifest.requires {
+            if !enabled_names.contains(required.as_str()) {
+                let state = if installed.iter().any(|p| &p.name == required) {
+                    "disabled"
+                } else {
+                    "not installed"
+                };
+                analysis.warnings.push(format!(
+                    "{} requires {}, which is {}",
+                    plugin.name, required, state
+                ));
+            }
+        }
+        for conflicting in &manifest.conflicts {
+            if enabled_names.contains(conflicting.as_str()) {
+                analysis.warnings.push(format!(
+                    "{} declares a conflict with {}, but both are enabled",
+                    plugin.name, conflicting
+                ));
+            }
+        }
+        if manifest.declares_dependencies() {
+            analysis
+                .dependencies
+                .push((plugin.name.clone(), manifest.clone()));
+        }
+    }
+    Ok(analysis)
+}
+
+/// All objects of [`OBJECT_KINDS`] defined in the plugin's data files.
+fn definitions_of(plugin_dir: &Path) -> Result<BTreeSet<Object>> {
+    let mut objects = BTreeSet::new();
+    let mut files = vec![];
+    collect_data_files(&plugin_dir.join("data"), &mut files)?;
+    for file in files {
+        let content = String::from_utf8_lossy(&fs::read(&file)?).into_owned();
+        for line in content.lines() {
+            if line.starts_with(|c: char| c.is_whitespace()) {
+                continue;
+            }
+            let tokens = tokenize(line);
+            let Some(kind) = tokens.first() else {
+                continue;
+            };
+            if !OBJECT_KINDS.contains(&kind.as_str()) {
+                continue;
+            }
+            // `ship "Base" "Variant"` defines the variant, not the base ship
+            if let Some(name) = tokens.get(1..).and_then(|names| names.last()) {
+                objects.insert((kind.clone(), name.clone()));
+            }
+        }
+    }
+    Ok(objects)
+}
+
+fn collect_data_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_data_files(&path, files)?;
+        } else if path.extension().and_then(OsStr::to_str) == Some("txt") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reads `plugin.txt`. Plugins without one (most older ones) declare nothing.
+pub fn read_manifest(plugin_dir: &Path) -> PluginManifest {
+    let mut manifest = PluginManifest::default();
+    let Ok(content) = fs::read_to_string(plugin_dir.join("plugin.txt")) else {
+        return manifest;
+    };
+    // The list (requires, optional, conflicted) the current lines belong to
+    let mut list: Option<String> = None;
+    for line in content.lines() {
+        let depth = line.chars().take_while(|c| *c == '\t').count();
+        let tokens = tokenize(line);
+        let Some(key) = tokens.first() else {
+            continue;
+        };
+        match depth {
+            0 => {
+                list = None;
+                if key == "name" {
+                    manifest.name = tokens.get(1).cloned();
+                }
+            }
+            1 => {
+                list = Some(key.clone());
+                if key == "game version" {
+                    manifest.game_version = tokens.get(1).cloned();
+                }
+            }
+            _ => match list.as_deref() {
+                Some("requires") => manifest.requires.push(key.clone()),
+                Some("optional") => manifest.optional.push(key.clone()),
+                Some("conflicted" | "conflicts") => manifest.conflicts.push(key.clone()),
+                _ => {}
+            },
+        }
+    }
+    manifest
+}
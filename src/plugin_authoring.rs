@@ -0,0 +1,167 @@
+use crate::data_check;
+use crate::plugins::{self, IndexEntry};
+use crate::saves::tokenize;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const ICON: &[u8] = include_bytes!("../assets/plugin-icon.png");
+const ICON_2X: &[u8] = include_bytes!("../assets/plugin-icon@2x.png");
+
+/// Creates an empty plugin with everything the game expects in `plugins_dir`, ready to be filled with content.
+pub fn create_scaffold(plugins_dir: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.contains('"') {
+        bail!("'{}' is not a valid plugin name", name);
+    }
+    let dir = plugins_dir.join(plugins::dir_name(name));
+    if dir.exists() {
+        bail!("{} already exists", dir.to_string_lossy());
+    }
+    fs::create_dir_all(dir.join("data"))?;
+    fs::create_dir_all(dir.join("images"))?;
+
+    fs::write(
+        dir.join("plugin.txt"),
+        format!("name \"{name}\"\nversion \"0.1.0\"\nauthors \"\"\nabout \"A short description of {name}.\"\n"),
+    )?;
+    fs::write(
+        dir.join("about.txt"),
+        format!("{name}\n\nDescribe what this plugin adds or changes.\n"),
+    )?;
+    fs::write(dir.join("icon.png"), ICON)?;
+    fs::write(dir.join("icon@2x.png"), ICON_2X)?;
+    fs::write(
+        dir.join("data")
+            .join(format!("{}.txt", plugins::dir_name(name))),
+        "# Data files use tabs for indentation. Everything in this folder is loaded by the game.\n\
+        # For example, this would add a new outfit:\n\
+        #\n\
+        # outfit \"Example Outfit\"\n\
+        # \tcategory \"Systems\"\n\
+        # \tcost 1000\n\
+        # \t\"mass\" 1\n\
+        # \t\"outfit space\" -1\n\
+        # \tdescription \"An outfit that does nothing at all.\"\n",
+    )?;
+    info!("Created plugin {} in {}", name, dir.to_string_lossy());
+    Ok(dir)
+}
+
+/// The top-level keys of plugin.txt with their first value.
+fn read_plugin_txt(plugin_dir: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(plugin_dir.join("plugin.txt"))
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let mut tokens = tokenize(line).into_iter();
+            Some((tokens.next()?, tokens.next()?))
+        })
+        .collect()
+}
+
+/// A release built by [`package`].
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub zip: PathBuf,
+    pub index_entry: PathBuf,
+}
+
+/// Checks the plugin with the game's data check, then zips it into the downloads folder together with
+/// an entry for the plugin index. The entry's `url` has to be filled in once the zip has been uploaded.
+pub fn package(plugin_dir: &Path, executable: &Path, config_dir: &Path) -> Result<Package> {
+    if plugin_dir.parent() != Some(config_dir.join("plugins").as_path()) {
+        bail!("Only enabled plugins are loaded by the data check, enable it first");
+    }
+    plugins::validate_layout(plugin_dir)?;
+    let dir_name = plugin_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow!("Invalid plugin directory"))?;
+    let manifest = read_plugin_txt(plugin_dir);
+    let version = manifest
+        .get("version")
+        .ok_or_else(|| anyhow!("plugin.txt has no version"))?;
+
+    let report = data_check::check_data(executable, config_dir)
+        .with_context(|| "Failed to run the data check")?;
+    if let Some(files) = report.errors.get(&dir_name) {
+        let count: usize = files.values().map(Vec::len).sum();
+        bail!(
+            "The data check found {} errors in {}: {}",
+            count,
+            dir_name,
+            files.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let out_dir = platform_dirs::UserDirs::new()
+        .ok_or_else(|| anyhow!("Failed to get the downloads folder"))?
+        .download_dir;
+    let zip = out_dir.join(format!("{dir_name}-{version}.zip"));
+    write_zip(plugin_dir, &dir_name, &zip)
+        .with_context(|| format!("Failed to write {}", zip.to_string_lossy()))?;
+
+    let description = fs::read_to_string(plugin_dir.join("about.txt")).unwrap_or_default();
+    let entry = IndexEntry {
+        name: manifest.get("name").cloned().unwrap_or(dir_name.clone()),
+        authors: manifest.get("authors").cloned().unwrap_or_default(),
+        homepage: String::new(),
+        license: String::new(),
+        url: String::new(),
+        version: version.clone(),
+        short_description: manifest.get("about").cloned().unwrap_or_default(),
+        description: description.trim().to_string(),
+        icon_url: None,
+        source: None,
+    };
+    let index_entry = out_dir.join(format!("{dir_name}-{version}.index-entry.json"));
+    serde_json::to_writer_pretty(File::create(&index_entry)?, &entry)?;
+
+    info!(
+        "Packaged plugin {} {} as {}",
+        dir_name,
+        version,
+        zip.to_string_lossy()
+    );
+    Ok(Package { zip, index_entry })
+}
+
+/// Zips the plugin into a single top-level folder, leaving out hidden files like the launcher's metadata.
+fn write_zip(plugin_dir: &Path, dir_name: &str, target: &Path) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(target)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut pending = vec![plugin_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                !p.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            })
+            .collect();
+        entries.sort();
+        for path in entries {
+            let relative = path
+                .strip_prefix(plugin_dir)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let name = format!("{dir_name}/{relative}");
+            if path.is_dir() {
+                zip.add_directory(name, options)?;
+                pending.push(path);
+            } else {
+                zip.start_file(name, options)?;
+                zip.write_all(&fs::read(&path)?)?;
+            }
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
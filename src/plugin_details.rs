@@ -0,0 +1,117 @@
+use crate::github::{self, ReleaseNotes};
+use crate::plugins::{CustomSource, IndexEntry, InstalledPlugin};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lists longer than this are cut off, some plugins ship thousands of images
+const MAX_LISTED_FILES: usize = 200;
+
+/// Everything about a plugin that is too expensive to gather for the whole list.
+#[derive(Debug, Clone, Default)]
+pub struct PluginDetails {
+    /// Paths relative to the plugin dir, with their size in bytes
+    pub files: Vec<(String, u64)>,
+    pub file_count: usize,
+    pub installed_size: Option<u64>,
+    pub download_size: Option<u64>,
+    pub about: Option<String>,
+    pub changelog: Option<String>,
+    pub release_notes: Vec<ReleaseNotes>,
+    pub screenshots: Vec<PathBuf>,
+}
+
+pub fn load(
+    entry: Option<&IndexEntry>,
+    installed: Option<&InstalledPlugin>,
+    custom_source: Option<&CustomSource>,
+) -> PluginDetails {
+    let mut details = PluginDetails::default();
+
+    if let Some(installed) = installed {
+        let mut files = vec![];
+        collect_files(&installed.path, &installed.path, &mut files);
+        files.sort();
+        details.installed_size = Some(files.iter().map(|(_, size)| size).sum());
+        details.file_count = files.len();
+        files.truncate(MAX_LISTED_FILES);
+        details.files = files;
+        details.about = fs::read_to_string(installed.path.join("about.txt")).ok();
+        details.changelog = ["CHANGELOG.md", "CHANGELOG.txt", "changelog.txt"]
+            .iter()
+            .find_map(|name| fs::read_to_string(installed.path.join(name)).ok());
+        details.screenshots = fs::read_dir(installed.path.join("screenshots"))
+            .map(|dir| {
+                dir.filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| {
+                        matches!(
+                            p.extension().and_then(OsStr::to_str),
+                            Some("png" | "jpg" | "jpeg")
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    if let Some(entry) = entry {
+        details.download_size = ureq::head(&entry.url)
+            .set("User-Agent", "ESLauncher2")
+            .call()
+            .ok()
+            .and_then(|res| res.header("Content-Length")?.parse().ok());
+    }
+
+    if let Some(repo) = github_repo(entry, custom_source) {
+        match github::get_release_notes(&repo) {
+            Ok(notes) => details.release_notes = notes,
+            Err(e) => debug!("Failed to get release notes of {}: {:#}", repo, e),
+        }
+    }
+    details
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, u64)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            files.push((relative.to_string_lossy().replace('\\', "/"), size));
+        }
+    }
+}
+
+/// The `owner/name` of the GitHub repository the plugin comes from, if it's hosted there.
+fn github_repo(entry: Option<&IndexEntry>, custom_source: Option<&CustomSource>) -> Option<String> {
+    if let Some(CustomSource::GitHub(repo)) = custom_source {
+        return Some(repo.clone());
+    }
+    let entry = entry?;
+    [&entry.homepage, &entry.url].iter().find_map(|url| {
+        let path = url.split_once("github.com/")?.1;
+        let mut segments = path.split('/');
+        let owner = segments.next().filter(|s| !s.is_empty())?;
+        let repo = segments.next().filter(|s| !s.is_empty())?;
+        Some(format!("{owner}/{}", repo.trim_end_matches(".git")))
+    })
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,228 @@
+use crate::get_data_dir;
+use crate::plugins::{self, IndexEntry, InstalledPlugin};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfilePlugin {
+    /// Directory name of the plugin
+    pub name: String,
+    /// `None` for plugins whose version the launcher doesn't know, any version will do
+    pub version: Option<String>,
+}
+
+/// A named set of enabled plugins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginProfile {
+    pub name: String,
+    pub plugins: Vec<ProfilePlugin>,
+}
+
+impl PluginProfile {
+    /// A profile of the currently enabled plugins.
+    pub fn from_installed(name: String, installed: &[InstalledPlugin]) -> Self {
+        Self {
+            name,
+            plugins: installed
+                .iter()
+                .filter(|p| p.enabled)
+                .map(|p| ProfilePlugin {
+                    name: p.name.clone(),
+                    version: p.version().map(String::from),
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether exactly this profile's plugins are enabled.
+    pub fn matches(&self, installed: &[InstalledPlugin]) -> bool {
+        let enabled: BTreeSet<&str> = installed
+            .iter()
+            .filter(|p| p.enabled)
+            .map(|p| p.name.as_str())
+            .collect();
+        enabled == self.plugins.iter().map(|p| p.name.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginProfiles {
+    pub profiles: Vec<PluginProfile>,
+    /// Plugins dir -> name of the profile last applied to it
+    pub applied: BTreeMap<PathBuf, String>,
+}
+
+impl PluginProfiles {
+    fn file() -> Result<PathBuf> {
+        Ok(get_data_dir()
+            .ok_or_else(|| anyhow!("Failed to get app save dir"))?
+            .join("plugin-profiles.json"))
+    }
+
+    pub fn load() -> Self {
+        let file = match Self::file() {
+            Ok(file) if file.exists() => file,
+            Ok(_) => return Self::default(),
+            Err(e) => {
+                warn!("{:#}", e);
+                return Self::default();
+            }
+        };
+        File::open(file)
+            .with_context(|| "Failed to open plugin-profiles.json")
+            .and_then(|f| {
+                serde_json::from_reader(f)
+                    .with_context(|| "Failed to deserialize plugin-profiles.json")
+            })
+            .unwrap_or_else(|e| {
+                warn!("{:#}", e);
+                Self::default()
+            })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        serde_json::to_writer_pretty(File::create(Self::file()?)?, self)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PluginProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Adds the profile, replacing any existing one with the same name.
+    pub fn insert(&mut self, profile: PluginProfile) {
+        self.profiles.retain(|p| p.name != profile.name);
+        self.profiles.push(profile);
+        self.profiles.sort_by_key(|p| p.name.to_lowercase());
+    }
+
+    /// `name`, or the first "name (n)" that no profile has yet.
+    pub fn free_name(&self, name: &str) -> String {
+        let mut free = name.to_string();
+        let mut n = 1;
+        while self.get(&free).is_some() {
+            n += 1;
+            free = format!("{name} ({n})");
+        }
+        free
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        self.applied.retain(|_, applied| applied != name);
+    }
+
+    /// Keeps the profiles applied to plugins dirs below `old_root` once those have moved to `new_root`.
+    pub fn relocate(&mut self, old_root: &Path, new_root: &Path) {
+        self.applied = std::mem::take(&mut self.applied)
+            .into_iter()
+            .map(|(dir, profile)| match dir.strip_prefix(old_root) {
+                Ok(relative) => (new_root.join(relative), profile),
+                Err(_) => (dir, profile),
+            })
+            .collect();
+    }
+
+    /// The profile that was last applied to `plugins_dir`, as long as nobody has changed the enabled plugins since.
+    pub fn active(&self, plugins_dir: &Path) -> Option<&PluginProfile> {
+        let profile = self.get(self.applied.get(plugins_dir)?)?;
+        let installed = plugins::installed_plugins(plugins_dir).ok()?;
+        profile.matches(&installed).then_some(profile)
+    }
+}
+
+/// Enables the profile's plugins and disables all others, installing missing plugins from the index.
+/// Returns warnings about plugins that couldn't be set up exactly as recorded.
+pub fn apply(
+    profile: &PluginProfile,
+    plugins_dir: &Path,
+    index: &[IndexEntry],
+) -> Result<Vec<String>> {
+    info!(
+        "Applying plugin profile {} to {}",
+        profile.name,
+        plugins_dir.to_string_lossy()
+    );
+    let installed = plugins::installed_plugins(plugins_dir)?;
+    for plugin in &installed {
+        let wanted = profile.plugins.iter().any(|p| p.name == plugin.name);
+        plugins::set_enabled(plugin, plugins_dir, wanted)?;
+    }
+
+    let mut warnings = vec![];
+    for wanted in &profile.plugins {
+        let current = installed.iter().find(|p| p.name == wanted.name);
+        if current
+            .is_some_and(|c| wanted.version.is_none() || c.version() == wanted.version.as_deref())
+        {
+            continue;
+        }
+        let entry = index
+            .iter()
+            .find(|e| plugins::dir_name(&e.name) == wanted.name);
+        let wanted_version = wanted.version.as_deref().unwrap_or("unknown");
+        match (current, entry) {
+            (_, Some(entry))
+                if wanted.version.is_none()
+                    || wanted.version.as_deref() == Some(&entry.version) =>
+            {
+                plugins::install(entry, plugins_dir, &|_| {})?;
+            }
+            (Some(current), _) => warnings.push(format!(
+                "{} {} is no longer available, keeping {}",
+                wanted.name,
+                wanted_version,
+                current.version().unwrap_or("the installed version")
+            )),
+            (None, Some(entry)) => {
+                warnings.push(format!(
+                    "{} {} is no longer available, installed {} instead",
+                    wanted.name, wanted_version, entry.version
+                ));
+                plugins::install(entry, plugins_dir, &|_| {})?;
+            }
+            (None, None) => warnings.push(format!(
+                "{} is neither installed nor in the plugin index",
+                wanted.name
+            )),
+        }
+    }
+
+    let mut profiles = PluginProfiles::load();
+    profiles
+        .applied
+        .insert(plugins_dir.to_path_buf(), profile.name.clone());
+    profiles.save()?;
+    Ok(warnings)
+}
+
+/// Writes the profile to the user's downloads folder, so it can be passed around.
+pub fn export(profile: &PluginProfile) -> Result<PathBuf> {
+    let dir = platform_dirs::UserDirs::new()
+        .ok_or_else(|| anyhow!("Failed to get the downloads folder"))?
+        .download_dir;
+    let file_name = profile.name.replace(['/', '\\', ':'], "_");
+    let path = dir.join(format!("{file_name}.plugin-profile.json"));
+    serde_json::to_writer_pretty(File::create(&path)?, profile)?;
+    info!(
+        "Exported plugin profile {} to {}",
+        profile.name,
+        path.to_string_lossy()
+    );
+    Ok(path)
+}
+
+pub fn import(path: &Path) -> Result<PluginProfile> {
+    let profile: PluginProfile = File::open(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|f| Ok(serde_json::from_reader(f)?))
+        .with_context(|| format!("Failed to read {}", path.to_string_lossy()))?;
+    if profile.name.trim().is_empty() {
+        bail!("The profile has no name");
+    }
+    Ok(profile)
+}
@@ -0,0 +1,830 @@
+use crate::instance::{format_timestamp, Progress};
+use crate::{archive, get_data_dir, github, saves, trash};
+use anyhow::{Context, Result};
+use core::fmt;
+use fs_extra::dir::CopyOptions;
+use lazy_static::lazy_static;
+use progress_streams::ProgressReader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::io::{copy, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+
+const INDEX_URL: &str =
+    "https://github.com/EndlessSkyCommunity/endless-sky-plugins/raw/master/generated/plugins.json";
+
+/// Written into every plugin directory the launcher installs, so it knows what it put there.
+/// The game ignores files it doesn't know about.
+const META_FILE: &str = ".eslauncher2.json";
+
+lazy_static! {
+    /// Versions of the plugins espim installed, before the launcher managed plugins itself.
+    /// `None` until espim could be asked.
+    static ref ESPIM_VERSIONS: Mutex<Option<BTreeMap<String, String>>> = Mutex::default();
+}
+
+/// A plugin as listed in the plugin index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    pub name: String,
+    #[serde(default)]
+    pub authors: String,
+    #[serde(default)]
+    pub homepage: String,
+    #[serde(default)]
+    pub license: String,
+    pub url: String,
+    pub version: String,
+    #[serde(default)]
+    pub short_description: String,
+    #[serde(default)]
+    pub description: String,
+    pub icon_url: Option<String>,
+    /// The additional index this entry comes from, `None` for the official one
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginMeta {
+    pub version: String,
+    pub url: String,
+    /// Unix timestamp
+    pub installed: i64,
+    /// Where to look for updates of plugins that aren't from the index
+    #[serde(default)]
+    pub custom_source: Option<CustomSource>,
+    /// Pinned plugins are left alone by bulk updates
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// Somewhere outside the index that a plugin can be installed from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CustomSource {
+    /// A zip file anywhere on the web
+    Url(String),
+    /// A GitHub repository (`owner/name`), installed from its latest release or default branch
+    GitHub(String),
+    /// A zip file or folder on this computer
+    Local(PathBuf),
+}
+
+impl CustomSource {
+    /// Accepts zip URLs, GitHub repository URLs and local paths.
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        if let Some(url) = input
+            .strip_prefix("https://")
+            .or_else(|| input.strip_prefix("http://"))
+        {
+            if let Some(path) = url
+                .strip_prefix("github.com/")
+                .or_else(|| url.strip_prefix("www.github.com/"))
+            {
+                let segments: Vec<&str> = path
+                    .trim_end_matches('/')
+                    .trim_end_matches(".git")
+                    .split('/')
+                    .collect();
+                if let [owner, repo] = segments[..] {
+                    return Ok(Self::GitHub(format!("{owner}/{repo}")));
+                }
+            }
+            return Ok(Self::Url(input.to_string()));
+        }
+
+        let path = PathBuf::from(input);
+        if path.is_dir()
+            || (path.is_file() && path.extension().and_then(OsStr::to_str) == Some("zip"))
+        {
+            Ok(Self::Local(path))
+        } else {
+            bail!(
+                "'{}' is neither a URL, nor a local zip file or folder",
+                input
+            )
+        }
+    }
+
+    /// The version an install from here would get, to tell whether the installed one is outdated.
+    pub fn latest_version(&self) -> Result<String> {
+        self.resolve().map(|resolved| resolved.version)
+    }
+
+    /// Finds out the plugin's name, current version and where to get it.
+    fn resolve(&self) -> Result<ResolvedSource> {
+        match self {
+            Self::Url(url) => {
+                let file_name = url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|u| u.trim_end_matches('/').rsplit('/').next())
+                    .unwrap_or(url);
+                let res = ureq::head(url).set("User-Agent", "ESLauncher2").call()?;
+                let version = res
+                    .header("ETag")
+                    .or_else(|| res.header("Last-Modified"))
+                    .unwrap_or("unknown")
+                    .trim_matches('"')
+                    .to_string();
+                Ok(ResolvedSource {
+                    name: file_name.trim_end_matches(".zip").replace("%20", " "),
+                    version,
+                    location: ArchiveLocation::Url(url.clone()),
+                })
+            }
+            Self::GitHub(repo) => {
+                let name = repo.rsplit('/').next().unwrap_or(repo).to_string();
+                match github::get_latest_release_of(repo) {
+                    Ok(release) => Ok(ResolvedSource {
+                        name,
+                        location: ArchiveLocation::Url(format!(
+                            "https://github.com/{repo}/archive/refs/tags/{}.zip",
+                            release.tag_name
+                        )),
+                        version: release.tag_name,
+                    }),
+                    Err(e) => {
+                        debug!("No release of {}, using its default branch: {:#}", repo, e);
+                        let branch = github::get_repository(repo)?.default_branch;
+                        let sha = github::get_commit(repo, &branch)?.sha;
+                        Ok(ResolvedSource {
+                            name,
+                            version: sha.chars().take(7).collect(),
+                            location: ArchiveLocation::Url(format!(
+                                "https://github.com/{repo}/archive/{sha}.zip"
+                            )),
+                        })
+                    }
+                }
+            }
+            Self::Local(path) => {
+                let modified = fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .with_context(|| format!("Failed to read {}", path.to_string_lossy()))?;
+                Ok(ResolvedSource {
+                    name: path
+                        .file_stem()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .ok_or_else(|| anyhow!("Invalid path"))?,
+                    version: format_timestamp(OffsetDateTime::from(modified).unix_timestamp()),
+                    location: if path.is_dir() {
+                        ArchiveLocation::Folder(path.clone())
+                    } else {
+                        ArchiveLocation::File(path.clone())
+                    },
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for CustomSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::GitHub(repo) => write!(f, "GitHub: {repo}"),
+            Self::Local(path) => write!(f, "{}", path.to_string_lossy()),
+        }
+    }
+}
+
+struct ResolvedSource {
+    name: String,
+    version: String,
+    location: ArchiveLocation,
+}
+
+enum ArchiveLocation {
+    Url(String),
+    File(PathBuf),
+    Folder(PathBuf),
+}
+
+impl fmt::Display for ArchiveLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::File(path) | Self::Folder(path) => write!(f, "{}", path.to_string_lossy()),
+        }
+    }
+}
+
+/// A plugin directory inside a config dir's `plugins` folder, or the launcher's disabled area next to it.
+/// Plugins installed by hand or by older launcher versions don't have any [`PluginMeta`].
+#[derive(Debug, Clone)]
+pub struct InstalledPlugin {
+    pub name: String,
+    pub path: PathBuf,
+    pub meta: Option<PluginMeta>,
+    /// For plugins the launcher didn't install, the version their plugin.txt or espim reports
+    pub legacy_version: Option<String>,
+    pub enabled: bool,
+    /// The version this one replaced, kept around for rolling back
+    pub previous: Option<Box<InstalledPlugin>>,
+}
+
+impl InstalledPlugin {
+    pub fn version(&self) -> Option<&str> {
+        self.meta
+            .as_ref()
+            .map(|m| m.version.as_str())
+            .or(self.legacy_version.as_deref())
+    }
+
+    pub fn pinned(&self) -> bool {
+        self.meta.as_ref().is_some_and(|m| m.pinned)
+    }
+
+    /// The plugin's own icon, if it ships one.
+    pub fn icon(&self) -> Option<Vec<u8>> {
+        ["icon.png", "icon@2x.png"]
+            .iter()
+            .find_map(|name| fs::read(self.path.join(name)).ok())
+    }
+}
+
+/// The last copy of an index that was retrieved successfully, for when the network is unavailable.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedIndex {
+    /// Unix timestamp
+    fetched: i64,
+    entries: Vec<IndexEntry>,
+}
+
+/// The plugins of all index sources, merged.
+#[derive(Debug, Clone)]
+pub struct LoadedIndex {
+    pub entries: Vec<IndexEntry>,
+    /// Remote indexes that couldn't be retrieved and were replaced by their cached copy,
+    /// with the time that copy was fetched
+    pub stale: BTreeMap<String, i64>,
+    /// Sources that couldn't be loaded, with the reason
+    pub failures: Vec<String>,
+}
+
+impl LoadedIndex {
+    /// When the cached copy of the official index was fetched, if it had to be used.
+    pub fn official_stale_since(&self) -> Option<i64> {
+        self.stale.get(INDEX_URL).copied()
+    }
+
+    /// The additional indexes that had to be replaced by their cached copy.
+    pub fn stale_sources(&self) -> BTreeMap<String, i64> {
+        let mut stale = self.stale.clone();
+        stale.remove(INDEX_URL);
+        stale
+    }
+}
+
+fn index_cache_file() -> Result<PathBuf> {
+    Ok(get_data_dir()
+        .ok_or_else(|| anyhow!("Failed to get app data dir"))?
+        .join("plugin-indexes.json"))
+}
+
+fn is_remote(source: &str) -> bool {
+    source.starts_with("https://") || source.starts_with("http://")
+}
+
+/// Accepts index URLs and local index files.
+pub fn parse_index_source(input: &str) -> Result<String> {
+    let input = input.trim();
+    if is_remote(input) || Path::new(input).is_file() {
+        Ok(input.to_string())
+    } else {
+        bail!("'{}' is neither a URL nor a local index file", input)
+    }
+}
+
+fn retrieve_index(source: &str) -> Result<Vec<IndexEntry>> {
+    if is_remote(source) {
+        debug!("Requesting {}", source);
+        ureq::get(source)
+            .set("User-Agent", "ESLauncher2")
+            .call()?
+            .into_json()
+            .with_context(|| format!("Failed to parse the plugin index {source}"))
+    } else {
+        File::open(source)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Ok(serde_json::from_reader(f)?))
+            .with_context(|| format!("Failed to read the plugin index {source}"))
+    }
+}
+
+/// Loads the `additional` indexes and the official one, falling back to cached copies of remote indexes
+/// that can't be retrieved. When several indexes contain the same plugin, the entry from the index
+/// listed first wins, and the official index comes last.
+pub fn load_index(additional: &[String]) -> Result<LoadedIndex> {
+    let cache_file = index_cache_file()?;
+    let mut cache: BTreeMap<String, CachedIndex> = File::open(&cache_file)
+        .ok()
+        .and_then(|f| serde_json::from_reader(f).ok())
+        .unwrap_or_default();
+
+    let mut loaded = LoadedIndex {
+        entries: vec![],
+        stale: BTreeMap::new(),
+        failures: vec![],
+    };
+    let mut any_loaded = false;
+    let mut seen = HashSet::new();
+    let sources = additional
+        .iter()
+        .map(|source| (source.as_str(), Some(source)))
+        .chain(std::iter::once((INDEX_URL, None)));
+    for (location, label) in sources {
+        let entries = match retrieve_index(location) {
+            Ok(entries) => {
+                if is_remote(location) {
+                    cache.insert(
+                        location.to_string(),
+                        CachedIndex {
+                            fetched: OffsetDateTime::now_utc().unix_timestamp(),
+                            entries: entries.clone(),
+                        },
+                    );
+                }
+                entries
+            }
+            Err(e) => {
+                loaded.failures.push(format!("{location}: {e:#}"));
+                match cache.get(location) {
+                    Some(cached) => {
+                        loaded.stale.insert(location.to_string(), cached.fetched);
+                        cached.entries.clone()
+                    }
+                    None => continue,
+                }
+            }
+        };
+        any_loaded = true;
+        for mut entry in entries {
+            if seen.insert(dir_name(&entry.name)) {
+                entry.source = label.cloned();
+                loaded.entries.push(entry);
+            } else {
+                debug!(
+                    "Ignoring {} from {}, an earlier index already has it",
+                    entry.name, location
+                );
+            }
+        }
+    }
+
+    if let Err(e) = File::create(&cache_file)
+        .map_err(anyhow::Error::from)
+        .and_then(|f| Ok(serde_json::to_writer(f, &cache)?))
+    {
+        warn!("Failed to cache the plugin indexes: {:#}", e);
+    }
+    if !any_loaded {
+        bail!(
+            "No plugin index could be loaded: {}",
+            loaded.failures.join(", ")
+        );
+    }
+    Ok(loaded)
+}
+
+pub fn retrieve_icon(url: &str) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    ureq::get(url)
+        .set("User-Agent", "ESLauncher2")
+        .call()?
+        .into_reader()
+        .read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Disabled plugins are moved here, where the game doesn't look for them.
+pub fn disabled_dir(plugins_dir: &Path) -> PathBuf {
+    plugins_dir.with_file_name("plugins-disabled")
+}
+
+/// The version a plugin had before its last update is moved here, where the game doesn't look for it.
+pub fn previous_dir(plugins_dir: &Path) -> PathBuf {
+    plugins_dir.with_file_name("plugins-previous")
+}
+
+fn read_meta(plugin_dir: &Path) -> Option<PluginMeta> {
+    File::open(plugin_dir.join(META_FILE))
+        .ok()
+        .and_then(|f| serde_json::from_reader(f).ok())
+}
+
+/// Asks espim which plugins it installed in which version. This retrieves espim's index,
+/// so it happens in the background and the versions show up once it's done.
+pub fn load_espim_versions() {
+    if ESPIM_VERSIONS.lock().unwrap().is_some() {
+        return;
+    }
+    match espim::retrieve_plugins() {
+        Ok(plugins) => {
+            let versions = plugins
+                .iter()
+                .filter(|p| p.is_installed())
+                .filter_map(|p| Some((dir_name(p.name()), p.versions().0?.to_string())))
+                .collect();
+            *ESPIM_VERSIONS.lock().unwrap() = Some(versions);
+        }
+        Err(e) => warn!("Failed to read the plugins installed by espim: {:#}", e),
+    }
+}
+
+/// The version of a plugin without launcher metadata, from its plugin.txt or from espim.
+fn read_legacy_version(name: &str, plugin_dir: &Path) -> Option<String> {
+    fs::read_to_string(plugin_dir.join("plugin.txt"))
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let tokens = saves::tokenize(line);
+                match &tokens[..] {
+                    [key, version] if key == "version" => Some(version.clone()),
+                    _ => None,
+                }
+            })
+        })
+        .or_else(|| ESPIM_VERSIONS.lock().unwrap().as_ref()?.get(name).cloned())
+}
+
+fn read_installed(name: String, path: PathBuf, enabled: bool) -> InstalledPlugin {
+    let meta = read_meta(&path);
+    InstalledPlugin {
+        legacy_version: meta
+            .is_none()
+            .then(|| read_legacy_version(&name, &path))
+            .flatten(),
+        meta,
+        name,
+        path,
+        enabled,
+        previous: None,
+    }
+}
+
+fn write_meta(plugin_dir: &Path, meta: &PluginMeta) -> Result<()> {
+    serde_json::to_writer_pretty(File::create(plugin_dir.join(META_FILE))?, meta)?;
+    Ok(())
+}
+
+/// All enabled and disabled plugins belonging to `plugins_dir`.
+pub fn installed_plugins(plugins_dir: &Path) -> Result<Vec<InstalledPlugin>> {
+    let mut plugins = vec![];
+    for (dir, enabled) in [
+        (plugins_dir.to_path_buf(), true),
+        (disabled_dir(plugins_dir), false),
+    ] {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if !path.is_dir() || name.starts_with('.') {
+                continue;
+            }
+            let previous_path = previous_dir(plugins_dir).join(&name);
+            let previous = previous_path
+                .is_dir()
+                .then(|| Box::new(read_installed(name.clone(), previous_path, false)));
+            plugins.push(InstalledPlugin {
+                previous,
+                ..read_installed(name, path, enabled)
+            });
+        }
+    }
+    plugins.sort_by_key(|p| p.name.to_lowercase());
+    Ok(plugins)
+}
+
+/// Plugin names become directory names, so keep them from escaping the plugins dir.
+pub fn dir_name(name: &str) -> String {
+    name.replace(['/', '\\', ':'], "_")
+        .trim_start_matches('.')
+        .to_string()
+}
+
+fn download(url: &str, target: &Path, on_progress: &dyn Fn(Progress)) -> Result<()> {
+    info!("Downloading {}", url);
+    on_progress(Progress::from("Downloading"));
+    let res = ureq::get(url).set("User-Agent", "ESLauncher2").call()?;
+    let total: Option<u32> = res
+        .header("Content-Length")
+        .and_then(|s| s.parse::<u32>().ok())
+        .map(|t| t / 1024);
+
+    let mut fetched = 0;
+    let mut last_report = Instant::now();
+    let mut reader = ProgressReader::new(res.into_reader(), |progress| {
+        fetched += progress;
+        if last_report.elapsed() >= Duration::from_millis(50) {
+            last_report = Instant::now();
+            on_progress(
+                Progress::from("Downloading")
+                    .done((fetched / 1024) as u32)
+                    .total(total)
+                    .units("KiB"),
+            );
+        }
+    });
+    copy(&mut reader, &mut File::create(target)?)?;
+    Ok(())
+}
+
+/// Downloads and extracts the plugin into `plugins_dir`, replacing any previous version.
+pub fn install(
+    entry: &IndexEntry,
+    plugins_dir: &Path,
+    on_progress: &dyn Fn(Progress),
+) -> Result<InstalledPlugin> {
+    install_archive(
+        &entry.name,
+        &entry.version,
+        &ArchiveLocation::Url(entry.url.clone()),
+        plugins_dir,
+        None,
+        on_progress,
+    )
+}
+
+/// Installs a plugin that isn't in the index, or updates one installed that way before.
+pub fn install_custom(
+    source: &CustomSource,
+    plugins_dir: &Path,
+    on_progress: &dyn Fn(Progress),
+) -> Result<InstalledPlugin> {
+    on_progress(Progress::from("Looking up"));
+    let resolved = source.resolve()?;
+    install_archive(
+        &resolved.name,
+        &resolved.version,
+        &resolved.location,
+        plugins_dir,
+        Some(source.clone()),
+        on_progress,
+    )
+}
+
+fn install_archive(
+    name: &str,
+    version: &str,
+    location: &ArchiveLocation,
+    plugins_dir: &Path,
+    custom_source: Option<CustomSource>,
+    on_progress: &dyn Fn(Progress),
+) -> Result<InstalledPlugin> {
+    info!(
+        "Installing plugin {} {} to {}",
+        name,
+        version,
+        plugins_dir.to_string_lossy()
+    );
+    fs::create_dir_all(plugins_dir)?;
+    let dir_name = dir_name(name);
+    let archive_file = plugins_dir.join(format!(".{dir_name}.zip"));
+    let staging_dir = plugins_dir.join(format!(".{dir_name}.partial"));
+
+    let result = match location {
+        ArchiveLocation::Url(url) => download(url, &archive_file, on_progress).and_then(|()| {
+            on_progress(Progress::from("Extracting"));
+            archive::unpack(&archive_file, &staging_dir, true)
+        }),
+        ArchiveLocation::File(file) => {
+            on_progress(Progress::from("Extracting"));
+            archive::unpack(file, &staging_dir, true)
+        }
+        ArchiveLocation::Folder(folder) => {
+            on_progress(Progress::from("Copying"));
+            let options = CopyOptions {
+                content_only: true,
+                ..CopyOptions::new()
+            };
+            fs_extra::dir::copy(folder, &staging_dir, &options)
+                .map(|_| ())
+                .map_err(anyhow::Error::from)
+        }
+    }
+    .and_then(|()| validate_layout(&staging_dir))
+    .and_then(|()| {
+        let enabled_path = plugins_dir.join(&dir_name);
+        let disabled_path = disabled_dir(plugins_dir).join(&dir_name);
+        // Updating a disabled plugin keeps it disabled
+        let enabled = enabled_path.exists() || !disabled_path.exists();
+        // Keep the current version for rollback. Any other copy would shadow the new version
+        // once the plugin is toggled.
+        let previous_path = previous_dir(plugins_dir).join(&dir_name);
+        for old in [&enabled_path, &disabled_path] {
+            if !old.exists() {
+                continue;
+            }
+            if previous_path.exists() {
+                fs::remove_dir_all(&previous_path)?;
+            }
+            fs::create_dir_all(previous_dir(plugins_dir))?;
+            fs::rename(old, &previous_path)?;
+        }
+        let target = if enabled { enabled_path } else { disabled_path };
+        fs::rename(&staging_dir, &target)?;
+        let meta = PluginMeta {
+            version: version.to_string(),
+            url: location.to_string(),
+            installed: OffsetDateTime::now_utc().unix_timestamp(),
+            custom_source,
+            pinned: false,
+        };
+        write_meta(&target, &meta)?;
+        Ok(InstalledPlugin {
+            name: dir_name.clone(),
+            path: target,
+            meta: Some(meta),
+            legacy_version: None,
+            enabled,
+            previous: previous_path
+                .exists()
+                .then(|| Box::new(read_installed(dir_name.clone(), previous_path, false))),
+        })
+    });
+
+    // Don't leave half-finished downloads behind, whether or not the install worked
+    fs::remove_file(&archive_file).ok();
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).ok();
+    }
+    result.with_context(|| format!("Failed to install {name}"))
+}
+
+/// Makes sure the extracted archive actually is a plugin, not e.g. a zip of the wrong folder.
+/// Images, sounds or an icon alone don't make one, the game needs data/ or a plugin.txt.
+pub fn validate_layout(plugin_dir: &Path) -> Result<()> {
+    if plugin_dir.join("data").is_dir() || plugin_dir.join("plugin.txt").is_file() {
+        Ok(())
+    } else {
+        bail!("This doesn't look like a plugin, it should contain a data folder or a plugin.txt")
+    }
+}
+
+/// Moves the plugin and the version it replaced to the trash.
+pub fn remove(plugin: &InstalledPlugin) -> Result<()> {
+    let paths = std::iter::once(&plugin.path)
+        .chain(plugin.previous.as_ref().map(|p| &p.path))
+        .cloned()
+        .collect();
+    trash::put(format!("Plugin {}", plugin.name), paths, None)
+        .with_context(|| format!("Failed to remove {}", plugin.path.to_string_lossy()))?;
+    info!("Removed plugin {}", plugin.name);
+    Ok(())
+}
+
+pub fn set_pinned(plugin: &InstalledPlugin, pinned: bool) -> Result<()> {
+    let mut meta = plugin
+        .meta
+        .clone()
+        .ok_or_else(|| anyhow!("The installed version of {} is unknown", plugin.name))?;
+    meta.pinned = pinned;
+    write_meta(&plugin.path, &meta)?;
+    info!(
+        "{} plugin {} at {}",
+        if pinned { "Pinned" } else { "Unpinned" },
+        plugin.name,
+        meta.version
+    );
+    Ok(())
+}
+
+/// Swaps the plugin with the version it replaced, so the rollback itself can be undone the same way.
+/// The restored version is pinned, so "Update all" doesn't immediately replace it again.
+pub fn rollback(plugin: &InstalledPlugin) -> Result<()> {
+    let previous = plugin
+        .previous
+        .as_ref()
+        .ok_or_else(|| anyhow!("There is no previous version of {}", plugin.name))?;
+    let swap = plugin
+        .path
+        .with_file_name(format!(".{}.rollback", plugin.name));
+    fs::rename(&plugin.path, &swap)?;
+    // The swap folder is hidden from the plugin list, so never leave the current version behind in it
+    if let Err(e) = fs::rename(&previous.path, &plugin.path) {
+        if let Err(e) = fs::rename(&swap, &plugin.path) {
+            error!("Failed to move {} back: {}", swap.to_string_lossy(), e);
+        }
+        return Err(e).with_context(|| format!("Failed to restore the previous {}", plugin.name));
+    }
+    if let Err(e) = fs::rename(&swap, &previous.path) {
+        if let Err(e) =
+            fs::rename(&plugin.path, &previous.path).and_then(|()| fs::rename(&swap, &plugin.path))
+        {
+            error!("Failed to undo the rollback of {}: {}", plugin.name, e);
+        }
+        return Err(e).with_context(|| format!("Failed to keep the current {}", plugin.name));
+    }
+    // Plugins the launcher didn't install get metadata now, otherwise they couldn't be pinned
+    let meta = previous.meta.clone().unwrap_or_else(|| PluginMeta {
+        version: previous.version().unwrap_or("unknown").to_string(),
+        url: String::new(),
+        installed: OffsetDateTime::now_utc().unix_timestamp(),
+        custom_source: None,
+        pinned: true,
+    });
+    write_meta(
+        &plugin.path,
+        &PluginMeta {
+            pinned: true,
+            ..meta
+        },
+    )?;
+    info!(
+        "Rolled back plugin {} from {} to {}",
+        plugin.name,
+        plugin.version().unwrap_or("unknown"),
+        previous.version().unwrap_or("unknown")
+    );
+    Ok(())
+}
+
+/// Moves the plugin between the plugins dir and the disabled area.
+pub fn set_enabled(plugin: &InstalledPlugin, plugins_dir: &Path, enabled: bool) -> Result<()> {
+    if plugin.enabled == enabled {
+        return Ok(());
+    }
+    let target_dir = if enabled {
+        plugins_dir.to_path_buf()
+    } else {
+        disabled_dir(plugins_dir)
+    };
+    let target = target_dir.join(&plugin.name);
+    if target.exists() {
+        bail!("{} already exists", target.to_string_lossy());
+    }
+    fs::create_dir_all(&target_dir)?;
+    fs::rename(&plugin.path, &target).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            plugin.path.to_string_lossy(),
+            target.to_string_lossy()
+        )
+    })?;
+    info!(
+        "{} plugin {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        plugin.name
+    );
+    Ok(())
+}
+
+/// A plugin to bring up to date, from the index or from where it was installed from.
+pub enum Update {
+    Index(IndexEntry),
+    /// The plugin's name and its source
+    Custom(String, CustomSource),
+}
+
+/// Installs all given plugins, running at most `concurrency` downloads at a time.
+/// Returns the outcome for each plugin, by name.
+pub fn install_all(
+    updates: Vec<Update>,
+    plugins_dir: &Path,
+    concurrency: usize,
+    on_progress: impl Fn(&str, Progress) + Sync,
+) -> Vec<(String, Result<()>)> {
+    let queue = Mutex::new(updates.into_iter());
+    let results = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| loop {
+                let Some(update) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let (name, result) = match update {
+                    Update::Index(entry) => {
+                        let result = install(&entry, plugins_dir, &|progress| {
+                            on_progress(&entry.name, progress);
+                        });
+                        (entry.name, result)
+                    }
+                    Update::Custom(name, source) => {
+                        let result = install_custom(&source, plugins_dir, &|progress| {
+                            on_progress(&name, progress);
+                        });
+                        (name, result)
+                    }
+                };
+                results.lock().unwrap().push((name, result.map(|_| ())));
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
@@ -1,18 +1,114 @@
-use crate::style::icon_button;
-use crate::{get_data_dir, style, Message};
-use anyhow::Context;
+use crate::config_location::ConfigLocation;
+use crate::icon_cache;
+use crate::instance::{format_timestamp, Instance, Progress};
+use crate::plugin_analysis::{self, PluginAnalysis};
+use crate::plugin_authoring;
+use crate::plugin_details::{self, PluginDetails};
+use crate::plugin_profiles::{self, PluginProfile, PluginProfiles};
+use crate::plugins::{self, CustomSource, IndexEntry, InstalledPlugin, Update};
+use crate::style::{hint_text, icon_button, text_button};
+use crate::{send_message, style, trash, Message};
 use anyhow::Result;
-use espim::Plugin as EspimPlugin;
-use iced::widget::{button, image, Column, Container, Image, Row, Scrollable, Space, Text};
+use core::fmt;
+use iced::widget::{
+    button, image, Column, Container, Image, PickList, Row, Scrollable, Space, Text, TextInput,
+};
 use iced::{alignment, theme, Alignment, Color, Command, Element, Length};
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::fs::File;
-use std::io::{Read, Write};
-use std::path::PathBuf;
-
-lazy_static! {
-    static ref CACHE_FILENAME_REGEX: Regex = Regex::new(r"[^\w.-]").unwrap();
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How many plugins "Update all" downloads at once
+const MAX_CONCURRENT_UPDATES: usize = 3;
+
+/// How many icons are downloaded at once
+const MAX_CONCURRENT_ICON_DOWNLOADS: usize = 8;
+
+/// How often to try reaching the plugin index again while offline
+const INDEX_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PluginFilter {
+    #[default]
+    All,
+    Installed,
+    NotInstalled,
+    UpdateAvailable,
+}
+
+impl PluginFilter {
+    pub const ALL: [Self; 4] = [
+        Self::All,
+        Self::Installed,
+        Self::NotInstalled,
+        Self::UpdateAvailable,
+    ];
+
+    fn matches(self, plugin: &Plugin) -> bool {
+        match self {
+            Self::All => true,
+            Self::Installed => plugin.installed.is_some(),
+            Self::NotInstalled => plugin.installed.is_none(),
+            Self::UpdateAvailable => plugin.has_update(),
+        }
+    }
+}
+
+impl fmt::Display for PluginFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "All"),
+            Self::Installed => write!(f, "Installed"),
+            Self::NotInstalled => write!(f, "Not installed"),
+            Self::UpdateAvailable => write!(f, "Update available"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PluginSort {
+    #[default]
+    Name,
+    /// When the launcher last installed or updated the plugin, the index doesn't have release dates
+    LastUpdated,
+    InstallState,
+}
+
+impl PluginSort {
+    pub const ALL: [Self; 3] = [Self::Name, Self::LastUpdated, Self::InstallState];
+}
+
+impl fmt::Display for PluginSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name => write!(f, "Name"),
+            Self::LastUpdated => write!(f, "Last updated"),
+            Self::InstallState => write!(f, "Install state"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ListMessage {
+    SearchChanged(String),
+    FilterSelected(PluginFilter),
+    SortSelected(PluginSort),
+    Refresh,
+}
+
+/// Where the plugin list came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexState {
+    Current,
+    /// The index couldn't be retrieved, so the cached copy fetched at this time is shown
+    Stale(i64),
+    /// Neither the index nor a cached copy were available
+    Unavailable,
 }
 
 #[derive(Debug, Clone)]
@@ -21,21 +117,1454 @@ pub enum PluginsFrameState {
     Ready { plugins: Vec<Plugin> },
 }
 
-impl PluginsFrameState {
-    pub fn new() -> (Self, Command<Message>) {
+#[derive(Debug, Clone)]
+pub struct PluginsFrame {
+    /// Whose plugins are shown and managed
+    location: ConfigLocation,
+    pub state: PluginsFrameState,
+    profiles: PluginProfiles,
+    /// The profile applied to the selected location, if its plugins are still the enabled ones
+    active_profile: Option<String>,
+    selected_profile: Option<String>,
+    new_profile_name: String,
+    import_path: String,
+    applying_profile: bool,
+    /// Outcome of the last profile action or bulk update
+    status: Option<String>,
+    search: String,
+    filter: PluginFilter,
+    pub sort: PluginSort,
+    /// URL or path of a plugin to add from outside the index
+    custom_input: String,
+    adding_custom: bool,
+    /// How far the plugin being added from a custom source has come
+    custom_progress: Option<Progress>,
+    /// The latest version of each custom source the installed plugins came from, once looked up
+    custom_versions: BTreeMap<String, String>,
+    new_plugin_name: String,
+    /// Name of the plugin being packaged
+    packaging: Option<String>,
+    /// Conflict and dependency report of the enabled plugins, `None` while hidden
+    analysis: Option<Result<PluginAnalysis, String>>,
+    analysing: bool,
+    expanded_conflict: Option<usize>,
+    /// Name of the plugin shown in the detail pane, with its details once they're loaded
+    details: Option<(String, Option<PluginDetails>)>,
+    index_state: IndexState,
+    /// Whether a background thread is waiting for the index to become reachable
+    retrying_index: bool,
+    /// Bumped whenever the plugin list is reloaded, retry threads of older generations stop
+    index_generation: Arc<AtomicUsize>,
+    /// Additional indexes shown from their cached copy, with the time it was fetched
+    stale_sources: BTreeMap<String, i64>,
+    /// Whether the plugin list is being loaded again
+    refreshing: bool,
+    /// Additional plugin indexes, in order of precedence
+    pub index_sources: Vec<String>,
+    /// Index sources that failed to load last time
+    index_failures: Vec<String>,
+    show_sources: bool,
+    source_input: String,
+    /// Size of the icon cache in bytes, as of opening the sources panel
+    icon_cache_size: Option<u64>,
+}
+
+/// The plugin list as loaded from a set of index sources.
+#[derive(Debug, Clone)]
+pub struct Catalogue {
+    plugins: Vec<Plugin>,
+    /// The state of the official index
+    state: IndexState,
+    /// Additional indexes shown from their cached copy, with the time it was fetched
+    stale_sources: BTreeMap<String, i64>,
+    failures: Vec<String>,
+    /// The additional index sources this was loaded from
+    sources: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BulkMessage {
+    UpdateAll,
+    UpdateAllFinished(Vec<(String, Result<(), String>)>),
+}
+
+#[derive(Debug, Clone)]
+pub enum AnalysisMessage {
+    Run,
+    Finished(Result<PluginAnalysis, String>),
+    TogglePair(usize),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum DetailMessage {
+    Loaded(String, PluginDetails),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum SourceMessage {
+    Toggle,
+    ClearIconCache,
+    Input(String),
+    Add,
+    Remove(usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuthoringMessage {
+    NameInput(String),
+    Create,
+    Package(String),
+    Packaged(Result<String, String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum CustomMessage {
+    Input(String),
+    Add,
+    Progress(Progress),
+    Added(Result<String, String>),
+    /// A custom source and the version it currently has
+    LatestVersion(String, String),
+}
+
+#[derive(Debug, Clone)]
+pub enum ProfileMessage {
+    Selected(String),
+    Apply,
+    Applied(Result<Vec<String>, String>),
+    NameInput(String),
+    SaveCurrent,
+    Delete,
+    Export,
+    ImportPathInput(String),
+    Import,
+}
+
+impl PluginsFrame {
+    pub fn new(sort: PluginSort, index_sources: Vec<String>) -> (Self, Command<Message>) {
+        let load = Command::perform(
+            load_plugins(index_sources.clone()),
+            Message::PluginFrameLoaded,
+        );
         (
-            Self::Loading,
-            Command::perform(load_plugins(), Message::PluginFrameLoaded),
+            Self {
+                location: ConfigLocation::default(),
+                state: PluginsFrameState::Loading,
+                profiles: PluginProfiles::load(),
+                active_profile: None,
+                selected_profile: None,
+                new_profile_name: String::new(),
+                import_path: String::new(),
+                applying_profile: false,
+                status: None,
+                search: String::new(),
+                filter: PluginFilter::default(),
+                sort,
+                custom_input: String::new(),
+                adding_custom: false,
+                custom_progress: None,
+                custom_versions: BTreeMap::new(),
+                new_plugin_name: String::new(),
+                packaging: None,
+                analysis: None,
+                analysing: false,
+                expanded_conflict: None,
+                details: None,
+                index_state: IndexState::Current,
+                retrying_index: false,
+                index_generation: Arc::default(),
+                stale_sources: BTreeMap::new(),
+                refreshing: false,
+                index_sources,
+                index_failures: vec![],
+                show_sources: false,
+                source_input: String::new(),
+                icon_cache_size: None,
+            },
+            load,
         )
     }
 
-    pub fn from(plugins: Vec<Plugin>) -> Self {
-        Self::Ready { plugins }
+    pub fn loaded(&mut self, catalogue: Catalogue, instances: &BTreeMap<String, Instance>) {
+        if catalogue.sources != self.index_sources {
+            debug!("Ignoring a plugin list loaded from outdated index sources");
+            return;
+        }
+        let Catalogue {
+            mut plugins,
+            state: index_state,
+            stale_sources,
+            failures,
+            ..
+        } = catalogue;
+        // A refreshed index may arrive while plugins are being worked on
+        if let PluginsFrameState::Ready { plugins: old } = &self.state {
+            for plugin in &mut plugins {
+                if let Some(old) = old.iter().find(|old| old.name == plugin.name) {
+                    plugin.state = old.state.clone();
+                    // Until the new icon arrives
+                    plugin.icon = old.icon.clone();
+                }
+            }
+        }
+        let entries: Vec<IndexEntry> = plugins.iter().filter_map(|p| p.entry.clone()).collect();
+        self.state = PluginsFrameState::Ready { plugins };
+        self.index_state = index_state;
+        self.index_failures = failures;
+        self.stale_sources = stale_sources;
+        self.refreshing = false;
+        self.refresh_installed(instances);
+        self.check_custom_sources();
+
+        // The list is usable without icons, so they are streamed in as they arrive
+        thread::spawn(move || {
+            icon_cache::load_all(&entries, MAX_CONCURRENT_ICON_DOWNLOADS, |entry, icon| {
+                send_message(Message::PluginIconLoaded(entry.name.clone(), icon));
+            });
+        });
+
+        if index_state == IndexState::Current && self.stale_sources.is_empty() {
+            self.retrying_index = false;
+        } else if !self.retrying_index {
+            self.retrying_index = true;
+            let sources = self.index_sources.clone();
+            let current_generation = self.index_generation.clone();
+            let generation = current_generation.load(Ordering::Relaxed);
+            thread::spawn(move || loop {
+                thread::sleep(INDEX_RETRY_INTERVAL);
+                // The list was reloaded in the meantime, possibly from other sources
+                if current_generation.load(Ordering::Relaxed) != generation {
+                    break;
+                }
+                match plugins::load_index(&sources) {
+                    Ok(loaded) if loaded.stale.is_empty() => {
+                        info!("The plugin indexes are reachable again");
+                        send_message(Message::PluginFrameLoaded(Catalogue {
+                            plugins: plugins_from_index(loaded.entries),
+                            state: IndexState::Current,
+                            stale_sources: BTreeMap::new(),
+                            failures: loaded.failures,
+                            sources,
+                        }));
+                        break;
+                    }
+                    Ok(loaded) => debug!(
+                        "Plugin indexes still unreachable: {}",
+                        loaded.stale.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ),
+                    Err(e) => debug!("The plugin indexes are still unreachable: {:#}", e),
+                }
+            });
+        }
     }
 
-    pub fn view(&self) -> Container<Message> {
-        match self {
-            Self::Loading => Container::new(
+    pub fn select_location(
+        &mut self,
+        location: ConfigLocation,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        self.location = location;
+        self.refresh_installed(instances);
+        self.check_custom_sources();
+        if let Some((name, _)) = &self.details {
+            return self.load_details(name.clone());
+        }
+        Command::none()
+    }
+
+    /// Keeps showing a renamed instance's plugins, whose files have moved.
+    pub fn instance_renamed(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        // The profile applied to the instance is recorded by its plugins dir
+        self.profiles = PluginProfiles::load();
+        if self.location.rename_instance(old_name, new_name) {
+            return self.select_location(self.location.clone(), instances);
+        }
+        Command::none()
+    }
+
+    /// Rescans the plugins dir of the selected location and updates each plugin's installed state.
+    /// Plugins that aren't in the index only show up while they're installed.
+    pub fn refresh_installed(&mut self, instances: &BTreeMap<String, Instance>) {
+        let PluginsFrameState::Ready { plugins } = &mut self.state else {
+            return;
+        };
+        let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+            error!("Failed to get plugins directory for {}", self.location);
+            self.location = ConfigLocation::Shared;
+            return;
+        };
+        let mut installed = plugins::installed_plugins(&plugins_dir).unwrap_or_else(|e| {
+            error!("Failed to read installed plugins: {:#}", e);
+            vec![]
+        });
+        self.active_profile = self
+            .profiles
+            .applied
+            .get(&plugins_dir)
+            .and_then(|name| self.profiles.get(name))
+            .filter(|profile| profile.matches(&installed))
+            .map(|profile| profile.name.clone());
+
+        // Unlisted plugins are rebuilt below, but may be in the middle of being worked on
+        let mut unlisted_states: BTreeMap<String, PluginState> = BTreeMap::new();
+        plugins.retain(|p| {
+            if p.entry.is_none() {
+                unlisted_states.insert(p.name.clone(), p.state.clone());
+            }
+            p.entry.is_some()
+        });
+        for plugin in plugins.iter_mut() {
+            let dir_name = plugin.entry.as_ref().map(|e| plugins::dir_name(&e.name));
+            plugin.installed = installed
+                .iter()
+                .position(|i| Some(&i.name) == dir_name.as_ref())
+                .map(|index| installed.remove(index));
+        }
+        for unlisted in installed {
+            let mut plugin = Plugin {
+                state: unlisted_states
+                    .remove(&unlisted.name)
+                    .unwrap_or(PluginState::Idle),
+                name: unlisted.name.clone(),
+                icon: unlisted.icon().map(image::Handle::from_memory),
+                entry: None,
+                installed: Some(unlisted),
+                confirm_remove: None,
+                custom_latest: None,
+            };
+            plugin.custom_latest = plugin
+                .custom_source()
+                .and_then(|source| self.custom_versions.get(&source.to_string()))
+                .cloned();
+            plugins.push(plugin);
+        }
+        plugins.sort_unstable_by_key(|p| p.name.to_lowercase());
+    }
+
+    /// Looks up the latest versions of the custom sources the installed plugins came from,
+    /// so they can be offered as updates like the ones from the index.
+    fn check_custom_sources(&self) {
+        let PluginsFrameState::Ready { plugins } = &self.state else {
+            return;
+        };
+        let sources: Vec<CustomSource> = plugins
+            .iter()
+            .filter_map(|p| p.custom_source())
+            .filter(|source| !self.custom_versions.contains_key(&source.to_string()))
+            .cloned()
+            .collect();
+        if sources.is_empty() {
+            return;
+        }
+        thread::spawn(move || {
+            for source in sources {
+                match source.latest_version() {
+                    Ok(version) => send_message(Message::PluginCustomMessage(
+                        CustomMessage::LatestVersion(source.to_string(), version),
+                    )),
+                    Err(e) => debug!(
+                        "Failed to look up the latest version of {}: {:#}",
+                        source, e
+                    ),
+                }
+            }
+        });
+    }
+
+    pub fn icon_loaded(&mut self, name: &str, icon: Vec<u8>) {
+        if let PluginsFrameState::Ready { plugins } = &mut self.state {
+            if let Some(plugin) = plugins.iter_mut().find(|p| p.name == name) {
+                plugin.icon = Some(image::Handle::from_memory(icon));
+            }
+        }
+    }
+
+    pub fn update_plugin(
+        &mut self,
+        name: &str,
+        message: PluginMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        if let PluginMessage::ShowDetails = message {
+            return self.load_details(name.to_string());
+        }
+        let plugins_dir = self.location.plugins_dir(instances);
+        let PluginsFrameState::Ready { plugins } = &mut self.state else {
+            return Command::none();
+        };
+        let Some(plugin) = plugins.iter_mut().find(|p| p.name == name) else {
+            error!("Failed to find internal Plug-In with name {}", name);
+            return Command::none();
+        };
+        let refresh = matches!(message, PluginMessage::WorkFinished(_));
+        let cmd = plugin.update(message, plugins_dir);
+        if refresh {
+            self.refresh_installed(instances);
+            // The installed files have changed
+            if self
+                .details
+                .as_ref()
+                .is_some_and(|(shown, _)| shown == name)
+            {
+                return Command::batch([cmd, self.load_details(name.to_string())]);
+            }
+        }
+        cmd
+    }
+
+    /// Opens the detail pane for the plugin and gathers its details in the background.
+    fn load_details(&mut self, name: String) -> Command<Message> {
+        let PluginsFrameState::Ready { plugins } = &self.state else {
+            return Command::none();
+        };
+        let Some(plugin) = plugins.iter().find(|p| p.name == name) else {
+            // The plugin is gone, e.g. an unlisted one that was removed
+            self.details = None;
+            return Command::none();
+        };
+        let entry = plugin.entry.clone();
+        let installed = plugin.installed.clone();
+        let source = plugin.custom_source().cloned();
+        self.details = Some((name.clone(), None));
+        Command::perform(
+            async move { plugin_details::load(entry.as_ref(), installed.as_ref(), source.as_ref()) },
+            move |details| Message::PluginDetailMessage(DetailMessage::Loaded(name, details)),
+        )
+    }
+
+    pub fn update_details(&mut self, message: DetailMessage) {
+        match message {
+            DetailMessage::Loaded(name, details) => {
+                // Ignore details of a plugin that is no longer shown
+                if let Some((shown, loaded)) = &mut self.details {
+                    if *shown == name {
+                        *loaded = Some(details);
+                    }
+                }
+            }
+            DetailMessage::Close => self.details = None,
+        }
+    }
+
+    fn index_state_view(&self) -> Option<Text> {
+        let text = match self.index_state {
+            IndexState::Current if self.stale_sources.is_empty() => return None,
+            IndexState::Current => format!(
+                "{} of the additional index sources couldn't be reached, see Index sources",
+                self.stale_sources.len()
+            ),
+            IndexState::Stale(fetched) => format!(
+                "Offline: showing the plugin list as of {}, it will refresh once the index is reachable again",
+                format_timestamp(fetched)
+            ),
+            IndexState::Unavailable => String::from(
+                "Offline: the plugin list couldn't be loaded, it will show up once the index is reachable",
+            ),
+        };
+        Some(
+            Text::new(text)
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.))),
+        )
+    }
+
+    fn details_view<'a>(&'a self, plugins: &'a [Plugin]) -> Option<Element<'a, Message>> {
+        let (name, details) = self.details.as_ref()?;
+        let plugin = plugins.iter().find(|p| &p.name == name)?;
+
+        let mut header = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new(&plugin.name).size(24))
+            .push(Space::new(Length::Fill, Length::Shrink));
+        if plugin.entry.is_some() {
+            header = header.push(
+                button::Button::new(style::href_icon())
+                    .style(icon_button())
+                    .on_press(Message::PluginMessage(
+                        plugin.name.clone(),
+                        PluginMessage::OpenHREF,
+                    )),
+            );
+        }
+        if plugin.installed.as_ref().is_some_and(|i| i.enabled) {
+            let mut package_button = button::Button::new(
+                Text::new(if self.packaging.as_ref() == Some(&plugin.name) {
+                    "Packaging..."
+                } else {
+                    "Package"
+                })
+                .size(14),
+            )
+            .style(text_button());
+            if self.packaging.is_none() {
+                package_button = package_button.on_press(Message::PluginAuthoringMessage(
+                    AuthoringMessage::Package(plugin.name.clone()),
+                ));
+            }
+            header = header.push(package_button);
+        }
+        header = header.push(
+            button::Button::new(style::close_icon())
+                .style(icon_button())
+                .on_press(Message::PluginDetailMessage(DetailMessage::Close)),
+        );
+
+        let mut column = Column::new().spacing(10).width(Length::Fill).push(header);
+        if let Some(entry) = &plugin.entry {
+            column = column
+                .push(hint_text(format!("By {}", entry.authors)))
+                .push(hint_text(format!("License: {}", entry.license)))
+                .push(hint_text(format!("Homepage: {}", entry.homepage)))
+                .push(Text::new(&entry.description).size(14));
+        }
+
+        let Some(details) = details else {
+            return Some(column.push(hint_text("Loading details...")).into());
+        };
+
+        let mut sizes = vec![];
+        if let Some(size) = details.download_size {
+            sizes.push(format!("Download: {}", plugin_details::format_size(size)));
+        }
+        if let Some(size) = details.installed_size {
+            sizes.push(format!(
+                "Installed: {} in {} files",
+                plugin_details::format_size(size),
+                details.file_count
+            ));
+        }
+        if !sizes.is_empty() {
+            column = column.push(hint_text(sizes.join(", ")));
+        }
+
+        if !details.screenshots.is_empty() {
+            column = details.screenshots.iter().fold(
+                column.push(Text::new("Screenshots")),
+                |column, screenshot| {
+                    column
+                        .push(Image::new(image::Handle::from_path(screenshot)).width(Length::Fill))
+                },
+            );
+        }
+        if let Some(about) = &details.about {
+            column = column
+                .push(Text::new("About"))
+                .push(Text::new(about.trim()).size(14));
+        }
+        if !details.release_notes.is_empty() {
+            column = details.release_notes.iter().fold(
+                column.push(Text::new("Release notes")),
+                |column, release| {
+                    let title = match &release.name {
+                        Some(name) if !name.is_empty() && *name != release.tag_name => {
+                            format!("{} ({})", release.tag_name, name)
+                        }
+                        _ => release.tag_name.clone(),
+                    };
+                    let date = release
+                        .published_at
+                        .as_deref()
+                        .and_then(|date| date.split('T').next())
+                        .unwrap_or_default();
+                    column
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(Text::new(title).size(14))
+                                .push(hint_text(date)),
+                        )
+                        .push(
+                            hint_text(release.body.as_deref().unwrap_or("No description").trim())
+                                .size(12),
+                        )
+                },
+            );
+        }
+        if let Some(changelog) = &details.changelog {
+            column = column
+                .push(Text::new("Changelog"))
+                .push(Text::new(changelog.trim()).size(12));
+        }
+        if !details.files.is_empty() {
+            column = details.files.iter().fold(
+                column.push(Text::new("Installed files")),
+                |column, (file, size)| {
+                    column.push(
+                        hint_text(format!("{file}  ({})", plugin_details::format_size(*size)))
+                            .size(12),
+                    )
+                },
+            );
+            if details.file_count > details.files.len() {
+                column = column.push(
+                    hint_text(format!(
+                        "and {} more",
+                        details.file_count - details.files.len()
+                    ))
+                    .size(12),
+                );
+            }
+        }
+        Some(column.into())
+    }
+
+    pub fn update_list(&mut self, message: ListMessage) -> Command<Message> {
+        match message {
+            ListMessage::SearchChanged(search) => self.search = search,
+            ListMessage::FilterSelected(filter) => self.filter = filter,
+            ListMessage::SortSelected(sort) => self.sort = sort,
+            ListMessage::Refresh => {
+                if !self.refreshing {
+                    info!("Refreshing the plugin list");
+                    return self.reload();
+                }
+            }
+        }
+        Command::none()
+    }
+
+    /// The plugins matching the search and filter, in display order.
+    fn visible<'a>(&self, plugins: &'a [Plugin]) -> Vec<&'a Plugin> {
+        let search = self.search.trim().to_lowercase();
+        let mut visible: Vec<&Plugin> = plugins
+            .iter()
+            .filter(|p| self.filter.matches(p) && (search.is_empty() || p.matches(&search)))
+            .collect();
+        // The list is kept sorted by name, so these stay alphabetical within each group
+        match self.sort {
+            PluginSort::Name => {}
+            PluginSort::LastUpdated => visible.sort_by_key(|p| {
+                Reverse(
+                    p.installed
+                        .as_ref()
+                        .and_then(|i| i.meta.as_ref())
+                        .map(|m| m.installed),
+                )
+            }),
+            PluginSort::InstallState => visible.sort_by_key(|p| p.install_rank()),
+        }
+        visible
+    }
+
+    /// The number of installed plugins in the selected location that have a different version available.
+    pub fn updates_available(&self) -> usize {
+        match &self.state {
+            PluginsFrameState::Ready { plugins } => {
+                plugins.iter().filter(|p| p.has_update()).count()
+            }
+            PluginsFrameState::Loading => 0,
+        }
+    }
+
+    pub fn update_bulk(
+        &mut self,
+        message: BulkMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        match message {
+            BulkMessage::UpdateAll => {
+                let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+                    error!("Failed to get plugins directory for {}", self.location);
+                    return Command::none();
+                };
+                let PluginsFrameState::Ready { plugins } = &mut self.state else {
+                    return Command::none();
+                };
+                let mut updates = vec![];
+                for plugin in plugins.iter_mut().filter(|p| p.has_update()) {
+                    if !matches!(plugin.state, PluginState::Idle | PluginState::Failed(_)) {
+                        continue;
+                    }
+                    let update = match (&plugin.entry, plugin.custom_source()) {
+                        (Some(entry), _) => Update::Index(entry.clone()),
+                        (None, Some(source)) => Update::Custom(plugin.name.clone(), source.clone()),
+                        (None, None) => continue,
+                    };
+                    updates.push(update);
+                    plugin.state = PluginState::Working(Progress::from("Queued"));
+                }
+                if updates.is_empty() {
+                    return Command::none();
+                }
+                info!("Updating {} plugins", updates.len());
+                self.status = Some(format!("Updating {} plugins...", updates.len()));
+                return Command::perform(
+                    async move {
+                        plugins::install_all(
+                            updates,
+                            &plugins_dir,
+                            MAX_CONCURRENT_UPDATES,
+                            |name, progress| {
+                                send_message(Message::PluginMessage(
+                                    name.to_string(),
+                                    PluginMessage::Progress(progress),
+                                ));
+                            },
+                        )
+                        .into_iter()
+                        .map(|(name, result)| (name, result.map_err(|e| format!("{e:#}"))))
+                        .collect()
+                    },
+                    |results| Message::PluginBulkMessage(BulkMessage::UpdateAllFinished(results)),
+                );
+            }
+            BulkMessage::UpdateAllFinished(results) => {
+                if let PluginsFrameState::Ready { plugins } = &mut self.state {
+                    for plugin in plugins.iter_mut() {
+                        if let Some((_, result)) =
+                            results.iter().find(|(name, _)| name == &plugin.name)
+                        {
+                            plugin.state = match result {
+                                Ok(()) => PluginState::Idle,
+                                Err(e) => PluginState::Failed(e.clone()),
+                            };
+                        }
+                    }
+                }
+                let failed: Vec<String> = results
+                    .iter()
+                    .filter_map(|(name, result)| {
+                        let e = result.as_ref().err()?;
+                        error!("Failed to update {}: {}", name, e);
+                        Some(name.clone())
+                    })
+                    .collect();
+                let updated = results.len() - failed.len();
+                info!("Updated {} plugins, {} failed", updated, failed.len());
+                self.status = Some(if failed.is_empty() {
+                    format!("Updated {updated} plugins")
+                } else {
+                    format!(
+                        "Updated {updated} plugins, {} failed: {}",
+                        failed.len(),
+                        failed.join(", ")
+                    )
+                });
+                self.refresh_installed(instances);
+            }
+        }
+        Command::none()
+    }
+
+    pub fn update_analysis(
+        &mut self,
+        message: AnalysisMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        match message {
+            AnalysisMessage::Run => {
+                let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+                    error!("Failed to get plugins directory for {}", self.location);
+                    return Command::none();
+                };
+                self.analysing = true;
+                return Command::perform(
+                    async move {
+                        plugins::installed_plugins(&plugins_dir)
+                            .and_then(|installed| plugin_analysis::analyse(&installed))
+                            .map_err(|e| format!("{e:#}"))
+                    },
+                    |result| Message::PluginAnalysisMessage(AnalysisMessage::Finished(result)),
+                );
+            }
+            AnalysisMessage::Finished(result) => {
+                self.analysing = false;
+                match &result {
+                    Ok(analysis) => info!(
+                        "Found {} plugin conflicts and {} dependency warnings",
+                        analysis.conflicts.len(),
+                        analysis.warnings.len()
+                    ),
+                    Err(e) => error!("Failed to analyse plugins: {}", e),
+                }
+                self.analysis = Some(result);
+                self.expanded_conflict = None;
+            }
+            AnalysisMessage::TogglePair(index) => {
+                self.expanded_conflict = if self.expanded_conflict == Some(index) {
+                    None
+                } else {
+                    Some(index)
+                };
+            }
+            AnalysisMessage::Close => self.analysis = None,
+        }
+        Command::none()
+    }
+
+    fn analysis_view(&self) -> Option<Element<AnalysisMessage>> {
+        let analysis = match self.analysis.as_ref()? {
+            Ok(analysis) => analysis,
+            Err(e) => return Some(hint_text(format!("Failed to check plugins: {e}")).into()),
+        };
+        let header = Row::new()
+            .align_items(Alignment::Center)
+            .push(Text::new(format!(
+                "{} conflicting plugin pairs, {} dependency warnings",
+                analysis.conflicts.len(),
+                analysis.warnings.len()
+            )))
+            .push(Space::new(Length::Fill, Length::Shrink))
+            .push(
+                button::Button::new(style::close_icon())
+                    .style(icon_button())
+                    .on_press(AnalysisMessage::Close),
+            );
+
+        let mut column = Column::new().spacing(5).width(Length::Fill).push(header);
+        for warning in &analysis.warnings {
+            column = column.push(
+                Text::new(warning)
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.))),
+            );
+        }
+        for (plugin, manifest) in &analysis.dependencies {
+            column = column.push(hint_text(format!(
+                "{plugin}: {}",
+                manifest.describe_dependencies()
+            )));
+        }
+        for (index, conflict) in analysis.conflicts.iter().enumerate() {
+            let (a, b) = &conflict.plugins;
+            column = column.push(
+                button::Button::new(
+                    Text::new(format!(
+                        "{a} and {b} both define {} objects",
+                        conflict.objects.len()
+                    ))
+                    .size(14),
+                )
+                .style(text_button())
+                .on_press(AnalysisMessage::TogglePair(index)),
+            );
+            if self.expanded_conflict == Some(index) {
+                column = conflict
+                    .objects
+                    .iter()
+                    .fold(column, |column, (kind, name)| {
+                        column.push(
+                            Container::new(hint_text(format!("{kind} \"{name}\"")).size(12))
+                                .padding(iced::Padding {
+                                    top: 0.0,
+                                    right: 0.0,
+                                    bottom: 0.0,
+                                    left: 15.0,
+                                }),
+                        )
+                    });
+            }
+        }
+        Some(column.into())
+    }
+
+    pub fn update_sources(&mut self, message: SourceMessage) -> Command<Message> {
+        match message {
+            SourceMessage::Toggle => {
+                self.show_sources = !self.show_sources;
+                self.icon_cache_size = icon_cache::size()
+                    .map_err(|e| warn!("Failed to get the icon cache size: {:#}", e))
+                    .ok();
+            }
+            SourceMessage::ClearIconCache => match icon_cache::clear() {
+                Ok(()) => {
+                    self.icon_cache_size = Some(0);
+                    self.status = Some(String::from("Cleared the icon cache"));
+                }
+                Err(e) => {
+                    error!("Failed to clear the icon cache: {:#}", e);
+                    self.status = Some(format!("{e:#}"));
+                }
+            },
+            SourceMessage::Input(input) => self.source_input = input,
+            SourceMessage::Add => match plugins::parse_index_source(&self.source_input) {
+                Ok(source) if self.index_sources.contains(&source) => {
+                    self.status = Some(format!("{source} is already an index source"));
+                }
+                Ok(source) => {
+                    info!("Added plugin index source {}", source);
+                    self.index_sources.push(source);
+                    self.source_input.clear();
+                    return self.reload();
+                }
+                Err(e) => self.status = Some(format!("{e:#}")),
+            },
+            SourceMessage::Remove(index) => {
+                if index < self.index_sources.len() {
+                    let source = self.index_sources.remove(index);
+                    info!("Removed plugin index source {}", source);
+                    return self.reload();
+                }
+            }
+        }
+        Command::none()
+    }
+
+    /// Loads the plugin list again from the current index sources, keeping the current one until then.
+    fn reload(&mut self) -> Command<Message> {
+        self.refreshing = true;
+        // Whatever is still retrying belongs to the list being replaced
+        self.index_generation.fetch_add(1, Ordering::Relaxed);
+        self.retrying_index = false;
+        Command::perform(
+            load_plugins(self.index_sources.clone()),
+            Message::PluginFrameLoaded,
+        )
+    }
+
+    fn sources_view(&self) -> Element<SourceMessage> {
+        let mut column = Column::new().spacing(5).push(hint_text(
+            "Additional plugin indexes, URLs or local files. \
+            When a plugin is in several indexes, the first one listed wins, the official index comes last.",
+        ));
+        for (index, source) in self.index_sources.iter().enumerate() {
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(source).size(14).width(Length::Fill))
+                    .push(
+                        button::Button::new(style::delete_icon())
+                            .style(icon_button())
+                            .on_press(SourceMessage::Remove(index)),
+                    ),
+            );
+        }
+        for (source, fetched) in &self.stale_sources {
+            column = column.push(
+                Text::new(format!(
+                    "Offline: showing {source} as of {}",
+                    format_timestamp(*fetched)
+                ))
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.))),
+            );
+        }
+        for failure in &self.index_failures {
+            column = column.push(
+                Text::new(format!("Failed to load {failure}"))
+                    .size(14)
+                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.))),
+            );
+        }
+        let mut add_button =
+            button::Button::new(Text::new("Add index").size(14)).style(text_button());
+        if !self.source_input.trim().is_empty() {
+            add_button = add_button.on_press(SourceMessage::Add);
+        }
+        let mut clear_button = button::Button::new(
+            Text::new(match self.icon_cache_size {
+                Some(size) => format!("Clear icon cache ({})", plugin_details::format_size(size)),
+                None => String::from("Clear icon cache"),
+            })
+            .size(14),
+        )
+        .style(text_button());
+        if self.icon_cache_size != Some(0) {
+            clear_button = clear_button.on_press(SourceMessage::ClearIconCache);
+        }
+        column
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new("Index URL or local index file", &self.source_input)
+                            .on_input(SourceMessage::Input)
+                            .on_submit(SourceMessage::Add)
+                            .padding(5)
+                            .size(14),
+                    )
+                    .push(add_button),
+            )
+            .push(clear_button)
+            .into()
+    }
+
+    pub fn update_custom(
+        &mut self,
+        message: CustomMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        match message {
+            CustomMessage::Input(input) => self.custom_input = input,
+            CustomMessage::Add => {
+                if self.adding_custom {
+                    return Command::none();
+                }
+                let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+                    error!("Failed to get plugins directory for {}", self.location);
+                    return Command::none();
+                };
+                let source = match CustomSource::parse(&self.custom_input) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        self.status = Some(format!("{e:#}"));
+                        return Command::none();
+                    }
+                };
+                self.adding_custom = true;
+                self.custom_progress = Some(Progress::from("Starting"));
+                self.status = Some(format!("Installing from {source}..."));
+                return Command::perform(
+                    async move {
+                        let on_progress = |progress: Progress| {
+                            send_message(Message::PluginCustomMessage(CustomMessage::Progress(
+                                progress,
+                            )));
+                        };
+                        plugins::install_custom(&source, &plugins_dir, &on_progress)
+                            .map(|installed| installed.name)
+                            .map_err(|e| format!("{e:#}"))
+                    },
+                    |result| Message::PluginCustomMessage(CustomMessage::Added(result)),
+                );
+            }
+            CustomMessage::Progress(progress) => {
+                // Progress may still trickle in after the install has finished
+                if self.adding_custom {
+                    self.custom_progress = Some(progress);
+                }
+            }
+            CustomMessage::Added(result) => {
+                self.adding_custom = false;
+                self.custom_progress = None;
+                self.status = Some(match result {
+                    Ok(name) => {
+                        self.custom_input.clear();
+                        info!("Installed plugin {}", name);
+                        format!("Installed {name}")
+                    }
+                    Err(e) => {
+                        error!("Failed to install plugin: {}", e);
+                        e
+                    }
+                });
+                self.refresh_installed(instances);
+                self.check_custom_sources();
+            }
+            CustomMessage::LatestVersion(source, version) => {
+                if let PluginsFrameState::Ready { plugins } = &mut self.state {
+                    for plugin in plugins.iter_mut().filter(|p| p.entry.is_none()) {
+                        if plugin
+                            .custom_source()
+                            .is_some_and(|s| s.to_string() == source)
+                        {
+                            plugin.custom_latest = Some(version.clone());
+                        }
+                    }
+                }
+                self.custom_versions.insert(source, version);
+            }
+        }
+        Command::none()
+    }
+
+    pub fn update_profiles(
+        &mut self,
+        message: ProfileMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+            error!("Failed to get plugins directory for {}", self.location);
+            return Command::none();
+        };
+        match message {
+            ProfileMessage::Selected(name) => self.selected_profile = Some(name),
+            ProfileMessage::Apply => {
+                let Some(profile) = self.selected().cloned() else {
+                    return Command::none();
+                };
+                let index: Vec<IndexEntry> = match &self.state {
+                    PluginsFrameState::Ready { plugins } => {
+                        plugins.iter().filter_map(|p| p.entry.clone()).collect()
+                    }
+                    PluginsFrameState::Loading => vec![],
+                };
+                self.applying_profile = true;
+                self.status = Some(format!("Applying {}...", profile.name));
+                return Command::perform(
+                    async move {
+                        plugin_profiles::apply(&profile, &plugins_dir, &index)
+                            .map_err(|e| format!("{e:#}"))
+                    },
+                    |result| Message::PluginProfileMessage(ProfileMessage::Applied(result)),
+                );
+            }
+            ProfileMessage::Applied(result) => {
+                self.applying_profile = false;
+                self.profiles = PluginProfiles::load();
+                self.status = Some(match result {
+                    Ok(warnings) if warnings.is_empty() => String::from("Profile applied"),
+                    Ok(warnings) => {
+                        for warning in &warnings {
+                            warn!("{}", warning);
+                        }
+                        format!("Profile applied with warnings:\n{}", warnings.join("\n"))
+                    }
+                    Err(e) => {
+                        error!("Failed to apply plugin profile: {}", e);
+                        format!("Failed to apply profile: {e}")
+                    }
+                });
+                self.refresh_installed(instances);
+            }
+            ProfileMessage::NameInput(name) => self.new_profile_name = name,
+            ProfileMessage::SaveCurrent => {
+                let name = self.new_profile_name.trim().to_string();
+                if name.is_empty() {
+                    return Command::none();
+                }
+                match plugins::installed_plugins(&plugins_dir) {
+                    Ok(installed) => {
+                        self.profiles
+                            .insert(PluginProfile::from_installed(name.clone(), &installed));
+                        self.profiles.applied.insert(plugins_dir, name.clone());
+                        self.save_profiles();
+                        self.selected_profile = Some(name);
+                        self.new_profile_name.clear();
+                        self.refresh_installed(instances);
+                    }
+                    Err(e) => error!("Failed to read installed plugins: {:#}", e),
+                }
+            }
+            ProfileMessage::Delete => {
+                if let Some(name) = self.selected_profile.take() {
+                    self.profiles.remove(&name);
+                    self.save_profiles();
+                    self.refresh_installed(instances);
+                }
+            }
+            ProfileMessage::Export => {
+                if let Some(profile) = self.selected() {
+                    self.status = Some(match plugin_profiles::export(profile) {
+                        Ok(path) => format!("Exported to {}", path.to_string_lossy()),
+                        Err(e) => {
+                            error!("Failed to export plugin profile: {:#}", e);
+                            format!("Failed to export profile: {e:#}")
+                        }
+                    });
+                }
+            }
+            ProfileMessage::ImportPathInput(path) => self.import_path = path,
+            ProfileMessage::Import => {
+                match plugin_profiles::import(Path::new(self.import_path.trim())) {
+                    Ok(mut profile) => {
+                        // Don't overwrite a profile of the same name
+                        let name = self.profiles.free_name(&profile.name);
+                        self.status = Some(if name == profile.name {
+                            format!("Imported {name}")
+                        } else {
+                            format!("Imported {} as {name}", profile.name)
+                        });
+                        profile.name = name;
+                        self.selected_profile = Some(profile.name.clone());
+                        self.profiles.insert(profile);
+                        self.save_profiles();
+                        self.import_path.clear();
+                    }
+                    Err(e) => {
+                        error!("Failed to import plugin profile: {:#}", e);
+                        self.status = Some(format!("Failed to import profile: {e:#}"));
+                    }
+                }
+            }
+        }
+        Command::none()
+    }
+
+    fn selected(&self) -> Option<&PluginProfile> {
+        self.profiles.get(self.selected_profile.as_ref()?)
+    }
+
+    fn save_profiles(&self) {
+        if let Err(e) = self.profiles.save() {
+            error!("Failed to save plugin-profiles.json: {:#}", e);
+        }
+    }
+
+    pub fn update_authoring(
+        &mut self,
+        message: AuthoringMessage,
+        instances: &BTreeMap<String, Instance>,
+    ) -> Command<Message> {
+        match message {
+            AuthoringMessage::NameInput(name) => self.new_plugin_name = name,
+            AuthoringMessage::Create => {
+                let Some(plugins_dir) = self.location.plugins_dir(instances) else {
+                    error!("Failed to get plugins directory for {}", self.location);
+                    return Command::none();
+                };
+                match plugin_authoring::create_scaffold(&plugins_dir, &self.new_plugin_name) {
+                    Ok(dir) => {
+                        self.status = Some(format!("Created {}", dir.to_string_lossy()));
+                        self.new_plugin_name.clear();
+                        self.refresh_installed(instances);
+                    }
+                    Err(e) => {
+                        error!("Failed to create plugin: {:#}", e);
+                        self.status = Some(format!("{e:#}"));
+                    }
+                }
+            }
+            AuthoringMessage::Package(name) => {
+                let PluginsFrameState::Ready { plugins } = &self.state else {
+                    return Command::none();
+                };
+                let Some(installed) = plugins
+                    .iter()
+                    .find(|p| p.name == name)
+                    .and_then(|p| p.installed.as_ref())
+                else {
+                    return Command::none();
+                };
+                let (Some(instance), Some(config_dir)) = (
+                    self.location.instance(instances),
+                    self.location.config_dir(instances),
+                ) else {
+                    self.status = Some(format!(
+                        "Packaging needs an instance using the {} location to run the data check",
+                        self.location
+                    ));
+                    return Command::none();
+                };
+                let plugin_dir = installed.path.clone();
+                let executable = instance.executable.clone();
+                self.packaging = Some(name);
+                self.status = Some(String::from("Checking and packaging..."));
+                return Command::perform(
+                    async move {
+                        plugin_authoring::package(&plugin_dir, &executable, &config_dir)
+                            .map(|package| {
+                                format!(
+                                    "Packaged as {}, fill in the url of {} once it's uploaded",
+                                    package.zip.to_string_lossy(),
+                                    package.index_entry.to_string_lossy()
+                                )
+                            })
+                            .map_err(|e| format!("{e:#}"))
+                    },
+                    |result| Message::PluginAuthoringMessage(AuthoringMessage::Packaged(result)),
+                );
+            }
+            AuthoringMessage::Packaged(result) => {
+                self.packaging = None;
+                self.status = Some(match result {
+                    Ok(status) => status,
+                    Err(e) => {
+                        error!("Failed to package plugin: {}", e);
+                        e
+                    }
+                });
+            }
+        }
+        Command::none()
+    }
+
+    fn authoring_view(&self) -> Element<AuthoringMessage> {
+        let mut create_button =
+            button::Button::new(Text::new("New plugin").size(14)).style(text_button());
+        if !self.new_plugin_name.trim().is_empty() {
+            create_button = create_button.on_press(AuthoringMessage::Create);
+        }
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                TextInput::new("Name of a new plugin to create", &self.new_plugin_name)
+                    .on_input(AuthoringMessage::NameInput)
+                    .on_submit(AuthoringMessage::Create)
+                    .padding(5)
+                    .size(14),
+            )
+            .push(create_button)
+            .into()
+    }
+
+    fn custom_view(&self) -> Element<CustomMessage> {
+        let mut add_button =
+            button::Button::new(Text::new("Add plugin").size(14)).style(text_button());
+        if !self.adding_custom && !self.custom_input.trim().is_empty() {
+            add_button = add_button.on_press(CustomMessage::Add);
+        }
+        let mut input = TextInput::new(
+            "Zip URL, GitHub repository or local zip/folder",
+            &self.custom_input,
+        )
+        .padding(5)
+        .size(14);
+        if !self.adding_custom {
+            input = input
+                .on_input(CustomMessage::Input)
+                .on_submit(CustomMessage::Add);
+        }
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(input)
+            .push_maybe(
+                self.custom_progress
+                    .as_ref()
+                    .map(|progress| progress.view().width(Length::Fixed(250.))),
+            )
+            .push(add_button)
+            .into()
+    }
+
+    fn profiles_view(&self) -> Element<ProfileMessage> {
+        let selected = self.selected();
+        let button = |label, message: ProfileMessage, enabled: bool| {
+            let mut button = button::Button::new(Text::new(label).size(14)).style(text_button());
+            if enabled {
+                button = button.on_press(message);
+            }
+            button
+        };
+
+        Column::new()
+            .spacing(5)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("Profile").size(14))
+                    .push(
+                        PickList::new(
+                            self.profiles
+                                .profiles
+                                .iter()
+                                .map(|p| p.name.clone())
+                                .collect::<Vec<String>>(),
+                            self.selected_profile.clone(),
+                            ProfileMessage::Selected,
+                        )
+                        .placeholder("Choose a profile")
+                        .text_size(14),
+                    )
+                    .push(button(
+                        "Apply",
+                        ProfileMessage::Apply,
+                        selected.is_some() && !self.applying_profile,
+                    ))
+                    .push(button("Export", ProfileMessage::Export, selected.is_some()))
+                    .push(button("Delete", ProfileMessage::Delete, selected.is_some()))
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(
+                        TextInput::new("New profile name", &self.new_profile_name)
+                            .on_input(ProfileMessage::NameInput)
+                            .on_submit(ProfileMessage::SaveCurrent)
+                            .padding(5)
+                            .size(14)
+                            .width(Length::Fixed(200.)),
+                    )
+                    .push(button(
+                        "Save enabled plugins",
+                        ProfileMessage::SaveCurrent,
+                        !self.new_profile_name.trim().is_empty(),
+                    )),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(hint_text(match &self.active_profile {
+                        Some(name) => format!("Active profile: {name}"),
+                        None => String::from("No profile active"),
+                    }))
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(
+                        TextInput::new("Path to a profile .json", &self.import_path)
+                            .on_input(ProfileMessage::ImportPathInput)
+                            .on_submit(ProfileMessage::Import)
+                            .padding(5)
+                            .size(14)
+                            .width(Length::Fixed(300.)),
+                    )
+                    .push(button(
+                        "Import",
+                        ProfileMessage::Import,
+                        !self.import_path.trim().is_empty(),
+                    )),
+            )
+            .push_maybe(self.status.as_ref().map(hint_text))
+            .into()
+    }
+
+    pub fn view(&self, instances: &BTreeMap<String, Instance>) -> Container<Message> {
+        let location_row = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new("Plugins for").size(14))
+            .push(
+                PickList::new(
+                    ConfigLocation::all(instances),
+                    Some(self.location.clone()),
+                    Message::PluginLocationSelected,
+                )
+                .text_size(14),
+            )
+            .push(Space::new(Length::Fill, Length::Shrink))
+            .push({
+                let mut button = button::Button::new(style::refresh_icon()).style(icon_button());
+                if !self.refreshing && !matches!(self.state, PluginsFrameState::Loading) {
+                    button = button.on_press(Message::PluginListMessage(ListMessage::Refresh));
+                }
+                button
+            })
+            .push(
+                button::Button::new(
+                    Text::new(if self.show_sources {
+                        "Hide index sources"
+                    } else {
+                        "Index sources"
+                    })
+                    .size(14),
+                )
+                .style(text_button())
+                .on_press(Message::PluginSourceMessage(SourceMessage::Toggle)),
+            )
+            .push({
+                let mut button = button::Button::new(
+                    Text::new(if self.analysing {
+                        "Checking..."
+                    } else {
+                        "Check conflicts"
+                    })
+                    .size(14),
+                )
+                .style(text_button());
+                if !self.analysing {
+                    button = button.on_press(Message::PluginAnalysisMessage(AnalysisMessage::Run));
+                }
+                button
+            })
+            .push({
+                let updates = self.updates_available();
+                let mut button = button::Button::new(
+                    Text::new(if updates > 0 {
+                        format!("Update all ({updates})")
+                    } else {
+                        String::from("Update all")
+                    })
+                    .size(14),
+                )
+                .style(text_button());
+                if updates > 0 {
+                    button = button.on_press(Message::PluginBulkMessage(BulkMessage::UpdateAll));
+                }
+                button
+            });
+
+        match &self.state {
+            PluginsFrameState::Loading => Container::new(
                 Column::new().align_items(Alignment::Center).push(
                     Text::new("Loading...")
                         .width(Length::Fill)
@@ -43,14 +1572,42 @@ impl PluginsFrameState {
                         .horizontal_alignment(alignment::Horizontal::Center),
                 ),
             ),
-            Self::Ready { plugins } => {
+            PluginsFrameState::Ready { plugins } => {
+                let visible = self.visible(plugins);
+                let list_row = Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        TextInput::new("Search name, author or description", &self.search)
+                            .on_input(|s| Message::PluginListMessage(ListMessage::SearchChanged(s)))
+                            .padding(5)
+                            .size(14),
+                    )
+                    .push(
+                        PickList::new(PluginFilter::ALL, Some(self.filter), |f| {
+                            Message::PluginListMessage(ListMessage::FilterSelected(f))
+                        })
+                        .text_size(14),
+                    )
+                    .push(Text::new("Sort by").size(14))
+                    .push(
+                        PickList::new(PluginSort::ALL, Some(self.sort), |s| {
+                            Message::PluginListMessage(ListMessage::SortSelected(s))
+                        })
+                        .text_size(14),
+                    )
+                    .push(hint_text(format!("{} of {}", visible.len(), plugins.len())));
                 let plugin_list =
-                    plugins.iter().fold(
+                    visible.into_iter().fold(
                         Column::new()
                             .padding(20)
                             .spacing(5)
                             .width(Length::Fill)
-                            .align_items(Alignment::Center),
+                            .align_items(Alignment::Center)
+                            .push_maybe(
+                                self.analysis_view()
+                                    .map(|view| view.map(Message::PluginAnalysisMessage)),
+                            ),
                         |column, plugin| {
                             column
                                 .push(iced::widget::horizontal_rule(2).style(
@@ -70,15 +1627,34 @@ impl PluginsFrameState {
                         },
                     );
 
+                let mut content =
+                    Row::new().push(Scrollable::new(plugin_list).width(Length::FillPortion(2)));
+                if let Some(details) = self.details_view(plugins) {
+                    content = content.push(iced::widget::vertical_rule(2)).push(
+                        Scrollable::new(Container::new(details).padding(20))
+                            .width(Length::FillPortion(3)),
+                    );
+                }
+
                 Container::new(
                     Column::new()
-                        .push(Scrollable::new(plugin_list))
+                        .push(location_row)
+                        .push(self.profiles_view().map(Message::PluginProfileMessage))
+                        .push(self.custom_view().map(Message::PluginCustomMessage))
+                        .push(self.authoring_view().map(Message::PluginAuthoringMessage))
+                        .push_maybe(
+                            self.show_sources
+                                .then(|| self.sources_view().map(Message::PluginSourceMessage)),
+                        )
+                        .push_maybe(self.index_state_view())
+                        .push(list_row)
+                        .push(content)
                         .spacing(20)
                         .width(Length::Fill),
                 )
                 .width(Length::Fill)
                 .padding(iced::Padding {
-                    top: 0.0,
+                    top: 10.0,
                     right: 30.0,
                     bottom: 0.0,
                     left: 30.0,
@@ -91,15 +1667,26 @@ impl PluginsFrameState {
 #[derive(Debug, Clone)]
 pub enum PluginMessage {
     Install,
+    /// Asks for confirmation first
     Remove,
+    RemovalSized(u64),
+    ConfirmRemove,
+    CancelRemove,
+    SetEnabled(bool),
+    SetPinned(bool),
+    Rollback,
     OpenHREF,
-    WorkFinished(Box<EspimPlugin>),
+    ShowDetails,
+    Progress(Progress),
+    WorkFinished(Result<(), String>),
 }
 
 #[derive(Debug, Clone)]
 pub enum PluginState {
-    Working,
-    Idle { espim_plugin: Box<EspimPlugin> },
+    Working(Progress),
+    Idle,
+    /// The last install failed for this reason
+    Failed(String),
 }
 
 #[derive(Debug, Clone)]
@@ -107,53 +1694,197 @@ pub struct Plugin {
     pub state: PluginState,
     pub name: String,
     icon: Option<image::Handle>,
+    /// `None` for plugins that aren't in the index
+    entry: Option<IndexEntry>,
+    /// Installed state in the selected location
+    installed: Option<InstalledPlugin>,
+    /// Set while the removal awaits confirmation, with the size once it's known
+    confirm_remove: Option<Option<u64>>,
+    /// The version its custom source currently has, for plugins that aren't in the index
+    custom_latest: Option<String>,
 }
 
 impl Plugin {
-    pub fn update(&mut self, message: PluginMessage) -> Command<Message> {
+    /// `search` is expected in lowercase.
+    fn matches(&self, search: &str) -> bool {
+        self.name.to_lowercase().contains(search)
+            || self.entry.as_ref().is_some_and(|e| {
+                [&e.authors, &e.short_description, &e.description]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(search))
+            })
+    }
+
+    /// Updatable first, then enabled, disabled and not installed plugins.
+    fn install_rank(&self) -> u8 {
+        match &self.installed {
+            _ if self.has_update() => 0,
+            Some(installed) if installed.enabled => 1,
+            Some(_) => 2,
+            None => 3,
+        }
+    }
+
+    /// Whether the index, or the custom source for plugins that aren't in it, has a different
+    /// version than the installed one.
+    /// Plugins of unknown version don't, they can still be installed over by hand.
+    /// Pinned plugins never do.
+    fn has_update(&self) -> bool {
+        let Some(installed) = self.installed.as_ref().filter(|i| !i.pinned()) else {
+            return false;
+        };
+        let available = match &self.entry {
+            Some(entry) => Some(entry.version.as_str()),
+            None => self.custom_latest.as_deref(),
+        };
+        match (installed.version(), available) {
+            (Some(version), Some(available)) => version != available,
+            _ => false,
+        }
+    }
+
+    /// Where a plugin that isn't from the index was installed from, so it can be updated from there.
+    fn custom_source(&self) -> Option<&CustomSource> {
+        self.installed
+            .as_ref()?
+            .meta
+            .as_ref()?
+            .custom_source
+            .as_ref()
+    }
+
+    fn update(&mut self, message: PluginMessage, plugins_dir: Option<PathBuf>) -> Command<Message> {
+        let modifies = matches!(
+            message,
+            PluginMessage::Install
+                | PluginMessage::Remove
+                | PluginMessage::ConfirmRemove
+                | PluginMessage::SetEnabled(_)
+                | PluginMessage::SetPinned(_)
+                | PluginMessage::Rollback
+        );
+        if modifies && matches!(self.state, PluginState::Working(_)) {
+            return Command::none();
+        }
         match message {
             PluginMessage::Install => {
-                if let PluginState::Idle { espim_plugin } = &mut self.state {
+                let entry = self.entry.clone();
+                let source = self.custom_source().cloned();
+                if let Some(plugins_dir) =
+                    plugins_dir.filter(|_| entry.is_some() || source.is_some())
+                {
                     let name = self.name.clone();
-                    let plugin = espim_plugin.clone();
-                    self.state = PluginState::Working;
-                    return Command::perform(perform_install(*plugin), move |p| {
-                        Message::PluginMessage(name, PluginMessage::WorkFinished(Box::new(p)))
-                    });
+                    self.state = PluginState::Working(Progress::from("Starting"));
+                    return Command::perform(
+                        perform_install(name.clone(), entry, source, plugins_dir),
+                        move |result| {
+                            Message::PluginMessage(name, PluginMessage::WorkFinished(result))
+                        },
+                    );
                 }
             }
             PluginMessage::Remove => {
-                if let PluginState::Idle { espim_plugin } = &mut self.state {
-                    espim_plugin.remove().unwrap_or_else(|e| {
-                        error!("Failed to remove Plug-In {}: {}", self.name, e);
+                if let Some(installed) = &self.installed {
+                    self.confirm_remove = Some(None);
+                    let name = self.name.clone();
+                    let paths: Vec<PathBuf> = std::iter::once(&installed.path)
+                        .chain(installed.previous.as_ref().map(|p| &p.path))
+                        .cloned()
+                        .collect();
+                    return Command::perform(
+                        async move { paths.iter().map(|p| trash::dir_size(p)).sum() },
+                        move |size| Message::PluginMessage(name, PluginMessage::RemovalSized(size)),
+                    );
+                }
+            }
+            PluginMessage::RemovalSized(size) => {
+                if self.confirm_remove.is_some() {
+                    self.confirm_remove = Some(Some(size));
+                }
+            }
+            PluginMessage::ConfirmRemove => {
+                self.confirm_remove = None;
+                if let Some(installed) = self.installed.clone() {
+                    return self.work("Removing", "remove", move || plugins::remove(&installed));
+                }
+            }
+            PluginMessage::CancelRemove => self.confirm_remove = None,
+            PluginMessage::SetEnabled(enabled) => {
+                if let (Some(installed), Some(plugins_dir)) = (self.installed.clone(), plugins_dir)
+                {
+                    let label = if enabled { "Enabling" } else { "Disabling" };
+                    return self.work(label, "toggle", move || {
+                        plugins::set_enabled(&installed, &plugins_dir, enabled)
+                    });
+                }
+            }
+            PluginMessage::SetPinned(pinned) => {
+                if let Some(installed) = self.installed.clone() {
+                    return self.work("Pinning", "pin", move || {
+                        plugins::set_pinned(&installed, pinned)
+                    });
+                }
+            }
+            PluginMessage::Rollback => {
+                if let Some(installed) = self.installed.clone() {
+                    return self.work("Rolling back", "roll back", move || {
+                        plugins::rollback(&installed)
                     });
                 }
             }
             PluginMessage::OpenHREF => {
-                if let PluginState::Idle { espim_plugin } = &mut self.state {
-                    if espim_plugin.is_available() {
-                        let url = espim_plugin
-                            .homepage()
-                            .unwrap_or("No homepage available".to_string());
-                        if url.trim().starts_with("http://") || url.trim().starts_with("https://") {
-                            if open::that(url.trim()).is_err() {
-                                error!("URL could not be opened: '{}'", url);
-                            }
-                        } else {
-                            error!("URL validation failed: '{}' is not http(s):// .", url);
+                if let Some(entry) = &self.entry {
+                    let url = entry.homepage.trim();
+                    if url.starts_with("http://") || url.starts_with("https://") {
+                        if open::that(url).is_err() {
+                            error!("URL could not be opened: '{}'", url);
                         }
+                    } else {
+                        error!("URL validation failed: '{}' is not http(s):// .", url);
                     }
                 }
             }
-            PluginMessage::WorkFinished(plugin) => {
-                self.state = PluginState::Idle {
-                    espim_plugin: plugin,
+            PluginMessage::Progress(progress) => {
+                // Progress may still trickle in after the work has finished
+                if let PluginState::Working(_) = self.state {
+                    self.state = PluginState::Working(progress);
+                }
+            }
+            PluginMessage::WorkFinished(result) => {
+                self.state = match result {
+                    Ok(()) => PluginState::Idle,
+                    Err(e) => PluginState::Failed(e),
                 };
             }
+            // Handled by the frame
+            PluginMessage::ShowDetails => {}
         }
         Command::none()
     }
 
+    /// Runs file operations on the plugin in the background, they may have to copy whole folders.
+    fn work(
+        &mut self,
+        label: &str,
+        action: &'static str,
+        work: impl FnOnce() -> Result<()> + Send + 'static,
+    ) -> Command<Message> {
+        let name = self.name.clone();
+        self.state = PluginState::Working(Progress::from(label));
+        Command::perform(
+            {
+                let name = name.clone();
+                async move {
+                    work().map_err(|e| {
+                        error!("Failed to {} Plug-In {}: {:#}", action, name, e);
+                        format!("{e:#}")
+                    })
+                }
+            },
+            move |result| Message::PluginMessage(name, PluginMessage::WorkFinished(result)),
+        )
+    }
+
     fn view(&self) -> Element<PluginMessage> {
         let content = Row::new().spacing(10).padding(10);
         const ICON_DIMENSION: f32 = 64.;
@@ -168,39 +1899,56 @@ impl Plugin {
             );
         }
         let mut textbox = Column::new().width(Length::Fill);
-        let mut titlebox = Column::new()
-            .push(Text::new(&self.name).vertical_alignment(alignment::Vertical::Center));
+        let mut titlebox = Column::new().push(
+            button::Button::new(
+                Text::new(&self.name).vertical_alignment(alignment::Vertical::Center),
+            )
+            .style(text_button())
+            .padding(0)
+            .on_press(PluginMessage::ShowDetails),
+        );
         let mut infos = Column::new();
 
         let mut controls = Row::new().spacing(10);
 
         match &self.state {
-            PluginState::Idle { espim_plugin } => {
-                let versions = espim_plugin.versions();
+            PluginState::Idle | PluginState::Failed(_) => {
                 titlebox = titlebox
                     .push(
-                        Text::new(if espim_plugin.is_installed() {
-                            format!("Installed: {}", versions.0.unwrap_or("unknown"))
-                        } else {
-                            String::from("Not installed")
+                        Text::new(match &self.installed {
+                            Some(installed) => format!(
+                                "Installed: {}{}{}",
+                                installed.version().unwrap_or("unknown"),
+                                if installed.pinned() { " (pinned)" } else { "" },
+                                if installed.enabled { "" } else { " (disabled)" }
+                            ),
+                            None => String::from("Not installed"),
                         })
                         .size(14)
                         .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
                     )
                     .push(
-                        Text::new(if espim_plugin.is_available() {
-                            format!("Available: {}", versions.1.unwrap_or("unknown"))
-                        } else {
-                            String::from("Unavailable")
+                        Text::new(match (&self.entry, self.custom_source()) {
+                            (Some(entry), _) => match &entry.source {
+                                Some(source) => {
+                                    format!("Available: {} from {source}", entry.version)
+                                }
+                                None => format!("Available: {}", entry.version),
+                            },
+                            (None, Some(source)) => match &self.custom_latest {
+                                Some(latest) => format!("Available: {latest} from {source}"),
+                                None => format!("Source: {source}"),
+                            },
+                            (None, None) => String::from("Unavailable"),
                         })
                         .size(14)
                         .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
                     );
                 infos = infos.push(Space::with_height(5)).push(
                     Text::new(
-                        espim_plugin
-                            .description()
-                            .unwrap_or("Not available".to_string()),
+                        self.entry
+                            .as_ref()
+                            .map_or("Not available", |e| e.description.as_str()),
                     )
                     .size(14)
                     .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
@@ -208,34 +1956,122 @@ impl Plugin {
 
                 let mut install_button =
                     button::Button::new(style::update_icon()).style(icon_button()); // TODO: Use other icon here?
-                if espim_plugin.is_available() {
+                let pinned = self.installed.as_ref().is_some_and(|i| i.pinned());
+                if (self.entry.is_some() || self.custom_source().is_some()) && !pinned {
                     install_button = install_button.on_press(PluginMessage::Install);
                 }
 
                 let mut remove_button =
                     button::Button::new(style::delete_icon()).style(theme::Button::Destructive);
-                if espim_plugin.is_installed() {
+                if self.installed.is_some() {
                     remove_button = remove_button.on_press(PluginMessage::Remove);
                 }
 
                 let mut href_button = button::Button::new(style::href_icon()).style(icon_button()); // TODO: Use other icon here?
-                if espim_plugin.is_available() {
+                if self.entry.is_some() {
                     href_button = href_button.on_press(PluginMessage::OpenHREF);
                 }
 
+                let mut toggle_button = button::Button::new(
+                    Text::new(match &self.installed {
+                        Some(installed) if !installed.enabled => "Enable",
+                        _ => "Disable",
+                    })
+                    .size(14),
+                )
+                .style(text_button());
+                if let Some(installed) = &self.installed {
+                    toggle_button =
+                        toggle_button.on_press(PluginMessage::SetEnabled(!installed.enabled));
+                }
+
+                let mut pin_button =
+                    button::Button::new(Text::new(if pinned { "Unpin" } else { "Pin" }).size(14))
+                        .style(text_button());
+                if self.installed.as_ref().is_some_and(|i| i.meta.is_some()) {
+                    pin_button = pin_button.on_press(PluginMessage::SetPinned(!pinned));
+                }
+
+                if let Some(previous) = self.installed.as_ref().and_then(|i| i.previous.as_ref()) {
+                    controls = controls.push(
+                        button::Button::new(
+                            Text::new(format!(
+                                "Roll back to {}",
+                                previous.version().unwrap_or("previous")
+                            ))
+                            .size(14),
+                        )
+                        .style(text_button())
+                        .on_press(PluginMessage::Rollback),
+                    );
+                }
                 controls = controls
+                    .push(pin_button)
+                    .push(toggle_button)
                     .push(href_button)
                     .push(install_button)
                     .push(remove_button);
+
+                if let PluginState::Failed(e) = &self.state {
+                    infos = infos.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(
+                                Text::new(format!("Install failed: {e}"))
+                                    .size(14)
+                                    .style(theme::Text::Color(Color::from_rgb(0.9, 0.3, 0.3)))
+                                    .width(Length::Fill),
+                            )
+                            .push(
+                                button::Button::new(Text::new("Retry").size(14))
+                                    .style(text_button())
+                                    .on_press(PluginMessage::Install),
+                            ),
+                    );
+                }
             }
-            PluginState::Working => {
-                infos = infos.push(
-                    Text::new("Working...")
-                        .size(14)
-                        .style(theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
-                );
+            PluginState::Working(progress) => {
+                infos = infos.push(progress.view().width(Length::Fixed(250.)));
             }
         };
+        if let (Some(size), Some(installed)) = (self.confirm_remove, &self.installed) {
+            let mut what = match size {
+                Some(size) => format!(
+                    "{} ({})",
+                    installed.path.to_string_lossy(),
+                    plugin_details::format_size(size)
+                ),
+                None => installed.path.to_string_lossy().into_owned(),
+            };
+            if let Some(previous) = &installed.previous {
+                match previous.version() {
+                    Some(version) => what.push_str(&format!(" and the previous version {version}")),
+                    None => what.push_str(" and the previous version"),
+                }
+            }
+            infos = infos.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Text::new(format!("Move {what} to the trash?"))
+                            .size(14)
+                            .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.)))
+                            .width(Length::Fill),
+                    )
+                    .push(
+                        button::Button::new(Text::new("Remove").size(14))
+                            .style(theme::Button::Destructive)
+                            .on_press(PluginMessage::ConfirmRemove),
+                    )
+                    .push(
+                        button::Button::new(Text::new("Cancel").size(14))
+                            .style(text_button())
+                            .on_press(PluginMessage::CancelRemove),
+                    ),
+            );
+        }
         let header = Row::new()
             .push(titlebox)
             .push(Space::new(Length::Fill, Length::Shrink))
@@ -246,85 +2082,83 @@ impl Plugin {
     }
 }
 
-pub async fn load_plugins() -> Vec<Plugin> {
-    let mut plugins = vec![];
-    match espim::retrieve_plugins() {
-        Ok(retrieved) => {
-            for p in retrieved {
-                let name = String::from(p.name());
-                let icon = load_icon_cached(&p)
-                    .map(image::Handle::from_memory)
-                    .map_err(|e| debug!("failed to fetch icon: {}", e))
-                    .ok();
-                plugins.push(Plugin {
-                    state: PluginState::Idle {
-                        espim_plugin: Box::new(p),
-                    },
-                    name,
-                    icon,
-                });
+pub async fn load_plugins(sources: Vec<String>) -> Catalogue {
+    plugins::load_espim_versions();
+    match plugins::load_index(&sources) {
+        Ok(loaded) => {
+            for failure in &loaded.failures {
+                warn!("Failed to load plugin index {}", failure);
+            }
+            // Only a complete index tells which icons are no longer needed
+            if loaded.failures.is_empty() {
+                if let Err(e) = icon_cache::prune(&loaded.entries) {
+                    warn!("Failed to prune the icon cache: {:#}", e);
+                }
+            }
+            Catalogue {
+                state: loaded
+                    .official_stale_since()
+                    .map_or(IndexState::Current, IndexState::Stale),
+                stale_sources: loaded.stale_sources(),
+                plugins: plugins_from_index(loaded.entries),
+                failures: loaded.failures,
+                sources,
             }
         }
         Err(e) => {
             error!(
-                "Failed to initialize ESPIM, Plug-Ins will be unavailable: {:#}",
+                "Failed to retrieve the plugin index, Plug-Ins will be unavailable: {:#}",
                 e
             );
+            Catalogue {
+                plugins: vec![],
+                state: IndexState::Unavailable,
+                stale_sources: BTreeMap::new(),
+                failures: vec![],
+                sources,
+            }
         }
     }
-    plugins.sort_unstable_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    plugins
 }
 
-fn get_cache_file(p: &EspimPlugin) -> Result<PathBuf> {
-    let cache_dir = get_data_dir().unwrap().join("icons");
-    if !(cache_dir.exists()) {
-        std::fs::create_dir(&cache_dir).with_context(|| "Failed to create icon cache")?;
-    }
-
-    let version = p.versions().0.unwrap_or_else(|| p.versions().1.unwrap());
-    let desired = format!("{}-{}", p.name(), version);
-    let filename = CACHE_FILENAME_REGEX.replace_all(&desired, "_");
-    Ok(cache_dir.join(&*filename))
-}
-
-fn load_icon_cached(p: &EspimPlugin) -> Result<Vec<u8>> {
-    if p.is_installed() {
-        return p
-            .retrieve_icon()
-            .ok_or_else(|| anyhow!("Failed to get item from installed plugin"));
-    }
-
-    let cache_file = get_cache_file(p)?;
-    if cache_file.exists() && cache_file.is_file() && !p.is_installed() {
-        let mut bytes = vec![];
-        File::open(cache_file)?.read_to_end(&mut bytes)?;
-        Ok(bytes)
-    } else {
-        let bytes = p
-            .retrieve_icon()
-            .with_context(|| "Failed to load icon from URL")?;
-        File::create(cache_file)?.write_all(&bytes)?;
-        Ok(bytes)
-    }
+fn plugins_from_index(entries: Vec<IndexEntry>) -> Vec<Plugin> {
+    let mut plugins: Vec<Plugin> = entries
+        .into_iter()
+        .map(|entry| Plugin {
+            state: PluginState::Idle,
+            name: entry.name.clone(),
+            icon: None,
+            entry: Some(entry),
+            installed: None,
+            confirm_remove: None,
+            custom_latest: None,
+        })
+        .collect();
+    plugins.sort_unstable_by_key(|p| p.name.to_lowercase());
+    plugins
 }
 
-pub async fn perform_install(mut plugin: EspimPlugin) -> EspimPlugin {
-    if plugin.is_installed() {
-        match get_cache_file(&plugin) {
-            Ok(old_cache_file) => {
-                if old_cache_file.exists() {
-                    let _ = std::fs::remove_file(old_cache_file);
-                }
-            }
-            Err(e) => {
-                error!("Failed to get cache filename: {}", e);
-            }
-        };
-    }
-
-    if let Err(e) = plugin.download() {
-        error!("Install failed: {:#}", e);
+/// Installs the plugin from the index, or from its custom source if it isn't in there.
+pub async fn perform_install(
+    name: String,
+    entry: Option<IndexEntry>,
+    source: Option<CustomSource>,
+    plugins_dir: PathBuf,
+) -> Result<(), String> {
+    let on_progress = |progress: Progress| {
+        send_message(Message::PluginMessage(
+            name.clone(),
+            PluginMessage::Progress(progress),
+        ));
+    };
+    match (entry, source) {
+        (Some(entry), _) => plugins::install(&entry, &plugins_dir, &on_progress),
+        (None, Some(source)) => plugins::install_custom(&source, &plugins_dir, &on_progress),
+        (None, None) => return Ok(()),
     }
-    plugin
+    .map(|_| ())
+    .map_err(|e| {
+        error!("Install of {} failed: {:#}", name, e);
+        format!("{e:#}")
+    })
 }
@@ -0,0 +1,347 @@
+use crate::get_data_dir;
+use anyhow::{Context, Result};
+use blake2::{Blake2s256, Digest};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use time::{format_description, OffsetDateTime};
+
+/// A snapshot of a saves directory.
+/// The files themselves are stored gzipped in a content-addressed object store next to the manifests,
+/// so unchanged saves don't take up additional space no matter how many snapshots reference them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    /// Unix timestamp
+    pub created: i64,
+    pub instance: String,
+    pub version: String,
+    pub saves_dir: PathBuf,
+    /// File name -> object hash
+    pub files: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    /// The pilots contained in this snapshot, i.e. the save files that aren't backups.
+    pub fn pilots(&self) -> Vec<&str> {
+        self.files
+            .keys()
+            .filter_map(|f| f.strip_suffix(".txt"))
+            .filter(|stem| !stem.contains('~'))
+            .collect()
+    }
+}
+
+fn get_snapshots_dir() -> Result<PathBuf> {
+    let dir = get_data_dir()
+        .ok_or_else(|| anyhow!("Failed to get app data dir"))?
+        .join("save-snapshots");
+    fs::create_dir_all(dir.join("objects"))?;
+    Ok(dir)
+}
+
+fn object_path(snapshots_dir: &Path, hash: &str) -> PathBuf {
+    snapshots_dir.join("objects").join(format!("{hash}.gz"))
+}
+
+/// Snapshots all save files in `saves_dir`, then prunes that directory's snapshots beyond the newest `retain` ones.
+pub fn snapshot_saves(
+    saves_dir: &Path,
+    instance: &str,
+    version: &str,
+    retain: usize,
+) -> Result<()> {
+    if retain == 0 || !saves_dir.exists() {
+        return Ok(());
+    }
+    let snapshots_dir = get_snapshots_dir()?;
+    if create_snapshot(&snapshots_dir, saves_dir, instance, version)?.is_some() {
+        prune(&snapshots_dir, saves_dir, retain, None)?;
+    }
+    Ok(())
+}
+
+/// Returns the id of the new snapshot, or `None` if there was nothing to snapshot.
+fn create_snapshot(
+    snapshots_dir: &Path,
+    saves_dir: &Path,
+    instance: &str,
+    version: &str,
+) -> Result<Option<String>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(saves_dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().and_then(OsStr::to_str) != Some("txt") {
+            continue;
+        }
+        let content = fs::read(&path)?;
+        let hash = format!("{:x}", Blake2s256::digest(&content));
+        let object = object_path(snapshots_dir, &hash);
+        if !object.exists() {
+            let mut encoder = GzEncoder::new(File::create(&object)?, Compression::default());
+            encoder.write_all(&content)?;
+            encoder.finish()?;
+        }
+        files.insert(
+            path.file_name().unwrap().to_string_lossy().into_owned(),
+            hash,
+        );
+    }
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let timestamp = now.format(&format_description::parse(
+        "[year]-[month]-[day] [hour]-[minute]-[second]",
+    )?)?;
+    // Several snapshots may be taken within the same second, e.g. right before a restore
+    let mut id = timestamp.clone();
+    let mut n = 1;
+    while snapshots_dir.join(format!("{id}.json")).exists() {
+        n += 1;
+        id = format!("{timestamp} {n}");
+    }
+    let snapshot = Snapshot {
+        id: id.clone(),
+        created: now.unix_timestamp(),
+        instance: instance.to_string(),
+        version: version.to_string(),
+        saves_dir: saves_dir.to_path_buf(),
+        files,
+    };
+    serde_json::to_writer_pretty(
+        File::create(snapshots_dir.join(format!("{id}.json")))?,
+        &snapshot,
+    )?;
+    info!(
+        "Snapshotted {} save files from {}",
+        snapshot.files.len(),
+        saves_dir.to_string_lossy()
+    );
+    Ok(Some(id))
+}
+
+/// All snapshots, newest first.
+pub fn list() -> Result<Vec<Snapshot>> {
+    list_in(&get_snapshots_dir()?)
+}
+
+fn list_in(snapshots_dir: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots = vec![];
+    for entry in fs::read_dir(snapshots_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(OsStr::to_str) != Some("json") {
+            continue;
+        }
+        match File::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Ok(serde_json::from_reader::<_, Snapshot>(f)?))
+        {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(e) => warn!("Skipping {}: {:#}", path.to_string_lossy(), e),
+        }
+    }
+    // Ids of snapshots taken within the same second get a counter appended, so they sort in creation order
+    snapshots.sort_by(|a, b| (b.created, b.id.len(), &b.id).cmp(&(a.created, a.id.len(), &a.id)));
+    Ok(snapshots)
+}
+
+/// Removes the snapshots of `saves_dir` beyond the newest `retain` ones, except `keep`,
+/// and then the objects no remaining snapshot of any saves directory references.
+fn prune(snapshots_dir: &Path, saves_dir: &Path, retain: usize, keep: Option<&str>) -> Result<()> {
+    let snapshots = list_in(snapshots_dir)?;
+    let mut retained = 0;
+    let mut remaining = vec![];
+    for snapshot in &snapshots {
+        if snapshot.saves_dir != saves_dir || keep == Some(snapshot.id.as_str()) {
+            remaining.push(snapshot);
+        } else if retained < retain {
+            retained += 1;
+            remaining.push(snapshot);
+        } else {
+            fs::remove_file(snapshots_dir.join(format!("{}.json", snapshot.id)))?;
+        }
+    }
+
+    let referenced: HashSet<&String> = remaining.iter().flat_map(|s| s.files.values()).collect();
+    for entry in fs::read_dir(snapshots_dir.join("objects"))? {
+        let path = entry?.path();
+        let hash = path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .and_then(|name| name.strip_suffix(".gz"))
+            .map(String::from);
+        if hash.is_some_and(|hash| !referenced.contains(&hash)) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Points the snapshots of saves directories below `old_root` to `new_root`, e.g. after an instance was renamed.
+pub fn relocate(old_root: &Path, new_root: &Path) -> Result<()> {
+    let snapshots_dir = get_snapshots_dir()?;
+    for mut snapshot in list_in(&snapshots_dir)? {
+        if let Ok(relative) = snapshot.saves_dir.strip_prefix(old_root) {
+            snapshot.saves_dir = new_root.join(relative);
+            serde_json::to_writer_pretty(
+                File::create(snapshots_dir.join(format!("{}.json", snapshot.id)))?,
+                &snapshot,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the pilot's save (and its backups) from the snapshot back to `saves_dir`,
+/// replacing the current files. The current state is snapshotted first, so restores can be undone.
+pub fn restore_pilot(
+    snapshot: &Snapshot,
+    pilot: &str,
+    saves_dir: &Path,
+    retain: usize,
+) -> Result<()> {
+    restore_pilot_in(&get_snapshots_dir()?, snapshot, pilot, saves_dir, retain)
+}
+
+fn restore_pilot_in(
+    snapshots_dir: &Path,
+    snapshot: &Snapshot,
+    pilot: &str,
+    saves_dir: &Path,
+    retain: usize,
+) -> Result<()> {
+    // Read everything before touching the snapshot store or the saves
+    let backup_prefix = format!("{pilot}~");
+    let mut restored = vec![];
+    for (file, hash) in &snapshot.files {
+        let stem = file.strip_suffix(".txt").unwrap_or(file);
+        if stem != pilot && !stem.starts_with(&backup_prefix) {
+            continue;
+        }
+        let mut content = vec![];
+        File::open(object_path(snapshots_dir, hash))
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Ok(GzDecoder::new(f).read_to_end(&mut content)?))
+            .with_context(|| format!("Failed to read {file} from snapshot"))?;
+        restored.push((file, content));
+    }
+    if restored.is_empty() {
+        bail!("Snapshot {} doesn't contain {}", snapshot.id, pilot);
+    }
+
+    fs::create_dir_all(saves_dir)?;
+    create_snapshot(snapshots_dir, saves_dir, "before restore", &snapshot.id)
+        .with_context(|| "Failed to snapshot the current saves")?;
+    for (file, content) in restored {
+        fs::write(saves_dir.join(file), content)?;
+    }
+    info!("Restored pilot {} from snapshot {}", pilot, snapshot.id);
+    // The restored snapshot stays, even if it's the oldest one
+    prune(snapshots_dir, saves_dir, retain.max(1), Some(&snapshot.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory holding a snapshot store and a saves directory.
+    fn temp_dirs(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("eslauncher2-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&root).ok();
+        let snapshots_dir = root.join("snapshots");
+        fs::create_dir_all(snapshots_dir.join("objects")).unwrap();
+        let saves_dir = root.join("saves");
+        fs::create_dir_all(&saves_dir).unwrap();
+        (snapshots_dir, saves_dir)
+    }
+
+    fn snapshot(snapshots_dir: &Path, saves_dir: &Path, content: &str) -> Snapshot {
+        fs::write(saves_dir.join("Jane Doe.txt"), content).unwrap();
+        let id = create_snapshot(snapshots_dir, saves_dir, "test", "1")
+            .unwrap()
+            .unwrap();
+        list_in(snapshots_dir)
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn snapshots_in_the_same_second_get_distinct_ids() {
+        let (snapshots_dir, saves_dir) = temp_dirs("ids");
+        let first = snapshot(
+            &snapshots_dir,
+            &saves_dir,
+            "pilot Jane Doe\ndate 1 1 3014\n",
+        );
+        let second = snapshot(
+            &snapshots_dir,
+            &saves_dir,
+            "pilot Jane Doe\ndate 2 1 3014\n",
+        );
+        assert_ne!(first.id, second.id);
+        let listed: Vec<String> = list_in(&snapshots_dir)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(listed, vec![second.id, first.id]);
+    }
+
+    #[test]
+    fn prune_counts_each_saves_dir_separately() {
+        let (snapshots_dir, saves_dir) = temp_dirs("prune");
+        let other_dir = saves_dir.with_file_name("other-saves");
+        fs::create_dir_all(&other_dir).unwrap();
+        let other = snapshot(&snapshots_dir, &other_dir, "other");
+        let old = snapshot(&snapshots_dir, &saves_dir, "old");
+        let new = snapshot(&snapshots_dir, &saves_dir, "new");
+
+        prune(&snapshots_dir, &saves_dir, 1, None).unwrap();
+
+        let ids: Vec<String> = list_in(&snapshots_dir)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(ids, vec![new.id.clone(), other.id.clone()]);
+        let object = |s: &Snapshot| object_path(&snapshots_dir, &s.files["Jane Doe.txt"]);
+        assert!(!object(&old).exists());
+        assert!(object(&new).exists());
+        assert!(object(&other).exists());
+    }
+
+    #[test]
+    fn restoring_the_oldest_snapshot_keeps_it() {
+        let (snapshots_dir, saves_dir) = temp_dirs("restore");
+        let old = snapshot(&snapshots_dir, &saves_dir, "old");
+        snapshot(&snapshots_dir, &saves_dir, "new");
+
+        restore_pilot_in(&snapshots_dir, &old, "Jane Doe", &saves_dir, 1).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(saves_dir.join("Jane Doe.txt")).unwrap(),
+            "old"
+        );
+        let snapshots = list_in(&snapshots_dir).unwrap();
+        assert!(snapshots.iter().any(|s| s.id == old.id));
+        // The state before the restore can be restored in turn
+        let before = &snapshots[0];
+        assert_eq!(before.instance, "before restore");
+        restore_pilot_in(&snapshots_dir, before, "Jane Doe", &saves_dir, 1).unwrap();
+        assert_eq!(
+            fs::read_to_string(saves_dir.join("Jane Doe.txt")).unwrap(),
+            "new"
+        );
+    }
+}
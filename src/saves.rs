@@ -0,0 +1,269 @@
+use crate::trash;
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A pilot is a save file like `Jane Doe.txt`, plus the backups and snapshots the game
+/// writes next to it (`Jane Doe~~previous-1.txt`, `Jane Doe~Before the war.txt`).
+#[derive(Debug, Clone)]
+pub struct Pilot {
+    /// The file stem shared by all files of this pilot
+    pub id: String,
+    pub file: PathBuf,
+    pub backups: Vec<PathBuf>,
+    pub info: SaveInfo,
+}
+
+impl Pilot {
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.file).chain(self.backups.iter())
+    }
+}
+
+/// The bits of a save file that are interesting enough to display.
+#[derive(Debug, Clone, Default)]
+pub struct SaveInfo {
+    pub name: Option<String>,
+    pub date: Option<String>,
+    pub system: Option<String>,
+    pub credits: Option<i64>,
+    pub ships: usize,
+}
+
+/// Splits a data file line into tokens, honouring `"` and `` ` `` quoting.
+pub fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '`' {
+            chars.next();
+            tokens.push(chars.by_ref().take_while(|&n| n != c).collect());
+        } else {
+            let mut token = String::new();
+            while let Some(&n) = chars.peek() {
+                if n.is_whitespace() {
+                    break;
+                }
+                token.push(n);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+pub fn parse_save(content: &str) -> SaveInfo {
+    let mut info = SaveInfo::default();
+    let mut in_account = false;
+    for line in content.lines() {
+        let indented = line.starts_with(|c: char| c.is_whitespace());
+        let tokens = tokenize(line);
+        let Some(key) = tokens.first() else {
+            continue;
+        };
+        if indented {
+            if in_account && key == "credits" {
+                info.credits = tokens.get(1).and_then(|c| c.parse().ok());
+            }
+            continue;
+        }
+        in_account = key == "account";
+        match key.as_str() {
+            "pilot" => info.name = Some(tokens[1..].join(" ")),
+            "date" if tokens.len() == 4 => {
+                info.date = Some(format!("{}.{}.{}", tokens[1], tokens[2], tokens[3]));
+            }
+            "system" => info.system = tokens.get(1).cloned(),
+            "ship" => info.ships += 1,
+            _ => {}
+        }
+    }
+    info
+}
+
+pub fn load_pilots(saves_dir: &Path) -> Result<Vec<Pilot>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(saves_dir)
+        .with_context(|| format!("Failed to read {}", saves_dir.to_string_lossy()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().and_then(OsStr::to_str) == Some("txt"))
+        .collect();
+    files.sort();
+
+    let mut pilots: Vec<Pilot> = vec![];
+    // Main files first, so backups always find their pilot
+    files.sort_by_key(|f| stem(f).contains('~'));
+    for file in files {
+        let stem = stem(&file);
+        match stem.split_once('~') {
+            Some((id, _)) => {
+                if let Some(pilot) = pilots.iter_mut().find(|p| p.id == id) {
+                    pilot.backups.push(file);
+                }
+            }
+            None => {
+                let info = parse_save(&fs::read_to_string(&file).unwrap_or_default());
+                pilots.push(Pilot {
+                    id: stem,
+                    file,
+                    backups: vec![],
+                    info,
+                });
+            }
+        }
+    }
+    pilots.sort_by_key(|p| p.id.to_lowercase());
+    Ok(pilots)
+}
+
+fn quote(token: &str) -> String {
+    if token.is_empty() || token.contains(char::is_whitespace) {
+        format!("\"{token}\"")
+    } else {
+        token.to_string()
+    }
+}
+
+fn stem(file: &Path) -> String {
+    file.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The path `file` would have if it belonged to the pilot `new_id` instead.
+fn renamed(file: &Path, old_id: &str, new_id: &str) -> PathBuf {
+    let stem = stem(file);
+    let suffix = stem.strip_prefix(old_id).unwrap_or_default();
+    file.with_file_name(format!("{new_id}{suffix}.txt"))
+}
+
+/// Copies all files of a pilot to `target_dir`, renaming them to `new_id` if given.
+/// Copies nothing if any of the files already exists there, and nothing stays behind if copying fails.
+pub fn copy_pilot(pilot: &Pilot, target_dir: &Path, new_id: Option<&str>) -> Result<()> {
+    let new_id = new_id.unwrap_or(&pilot.id);
+    let mut copies = vec![];
+    for file in pilot.files() {
+        let target = target_dir.join(
+            renamed(file, &pilot.id, new_id)
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid save file name"))?,
+        );
+        if target.exists() {
+            bail!("{} already exists", target.to_string_lossy());
+        }
+        copies.push((file, target));
+    }
+    fs::create_dir_all(target_dir)?;
+    for (index, (file, target)) in copies.iter().enumerate() {
+        if let Err(e) = fs::copy(file, target) {
+            // Including the one that failed halfway, none of them existed before
+            for (_, copied) in &copies[..=index] {
+                fs::remove_file(copied).ok();
+            }
+            return Err(e).with_context(|| format!("Failed to copy {}", file.to_string_lossy()));
+        }
+    }
+    Ok(())
+}
+
+pub fn duplicate_pilot(pilot: &Pilot) -> Result<()> {
+    let dir = pilot
+        .file
+        .parent()
+        .ok_or_else(|| anyhow!("Save file has no parent directory"))?;
+    let mut n = 1;
+    let new_id = loop {
+        let candidate = if n == 1 {
+            format!("{} copy", pilot.id)
+        } else {
+            format!("{} copy {}", pilot.id, n)
+        };
+        if !dir.join(format!("{candidate}.txt")).exists() {
+            break candidate;
+        }
+        n += 1;
+    };
+    copy_pilot(pilot, dir, Some(&new_id))?;
+    info!("Duplicated pilot {} as {}", pilot.id, new_id);
+    Ok(())
+}
+
+/// Renames the save files and the pilot name stored inside the main save.
+pub fn rename_pilot(pilot: &Pilot, new_name: &str) -> Result<()> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name.contains(['~', '/', '\\', ':', '"']) {
+        bail!("'{}' is not a valid pilot name", new_name);
+    }
+    if pilot
+        .file
+        .with_file_name(format!("{new_name}.txt"))
+        .exists()
+    {
+        bail!("A pilot named {} already exists", new_name);
+    }
+
+    // The game stores first and last name as separate tokens
+    let (first, last) = new_name.split_once(' ').unwrap_or((new_name, ""));
+    let pilot_line = format!("pilot {} {}", quote(first), quote(last.trim()));
+    let content = fs::read_to_string(&pilot.file)?;
+    let renamed_content: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if line.starts_with("pilot ") {
+                pilot_line.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+
+    // Rename the files first, so a failure leaves the pilot as it was
+    let renames: Vec<(&PathBuf, PathBuf)> = pilot
+        .files()
+        .map(|file| (file, renamed(file, &pilot.id, new_name)))
+        .collect();
+    // Renaming would silently replace them
+    if let Some((_, taken)) = renames.iter().find(|(_, target)| target.exists()) {
+        bail!("{} already exists", taken.to_string_lossy());
+    }
+    let undo = |done: &[(&PathBuf, PathBuf)]| {
+        for (file, target) in done.iter().rev() {
+            if let Err(e) = fs::rename(target, file) {
+                error!("Failed to rename {} back: {}", target.to_string_lossy(), e);
+            }
+        }
+    };
+    for (index, (file, target)) in renames.iter().enumerate() {
+        if let Err(e) = fs::rename(file, target) {
+            undo(&renames[..index]);
+            return Err(e).with_context(|| format!("Failed to rename {}", file.to_string_lossy()));
+        }
+    }
+
+    // Then swap in the new name inside the save in one go
+    let new_file = renamed(&pilot.file, &pilot.id, new_name);
+    let partial = new_file.with_extension("txt.partial");
+    if let Err(e) = fs::write(&partial, renamed_content.join("\n") + "\n")
+        .and_then(|()| fs::rename(&partial, &new_file))
+    {
+        fs::remove_file(&partial).ok();
+        undo(&renames);
+        return Err(e).with_context(|| format!("Failed to write {}", new_file.to_string_lossy()));
+    }
+    info!("Renamed pilot {} to {}", pilot.id, new_name);
+    Ok(())
+}
+
+/// Moves the pilot and its backups to the trash, where it can be restored from.
+pub fn delete_pilot(pilot: &Pilot) -> Result<()> {
+    trash::put(
+        format!("Pilot {}", pilot.id),
+        pilot.files().cloned().collect(),
+        None,
+    )?;
+    info!("Deleted pilot {}", pilot.id);
+    Ok(())
+}
@@ -1,12 +1,39 @@
 use crate::get_data_dir;
+use crate::instances_frame::InstanceSort;
 use crate::music::MusicState;
+use crate::plugins_frame::PluginSort;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub music_state: MusicState,
+    pub instance_sort: InstanceSort,
+    pub plugin_sort: PluginSort,
+    /// Game logs older than this can be pruned from the log browser
+    pub log_retention_days: u32,
+    /// How many save snapshots to keep, 0 disables snapshotting
+    pub save_snapshot_count: usize,
+    /// Plugin index URLs or local index files besides the official index, in order of precedence
+    pub plugin_index_sources: Vec<String>,
+    /// Deleted instances and plugins stay restorable for this long
+    pub trash_retention_days: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_state: MusicState::default(),
+            instance_sort: InstanceSort::default(),
+            plugin_sort: PluginSort::default(),
+            log_retention_days: 30,
+            save_snapshot_count: 20,
+            plugin_index_sources: vec![],
+            trash_retention_days: 7,
+        }
+    }
 }
 
 impl Settings {
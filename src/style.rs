@@ -1,6 +1,7 @@
 use iced::border::Radius;
 use iced::widget::{button, container, Text};
 use iced::{alignment, Background, Border, Color, Font, Length, Theme, Vector};
+use log::Level;
 use std::rc::Rc;
 
 fn icon(unicode: char) -> Text<'static> {
@@ -39,6 +40,45 @@ pub fn folder_icon() -> Text<'static> {
     icon('\u{E930}')
 }
 
+pub fn logs_icon() -> Text<'static> {
+    icon('\u{E926}')
+}
+
+pub fn tests_icon() -> Text<'static> {
+    icon('\u{EA10}')
+}
+
+pub fn check_data_icon() -> Text<'static> {
+    icon('\u{EA12}')
+}
+
+pub fn refresh_icon() -> Text<'static> {
+    icon('\u{E984}')
+}
+
+pub fn close_icon() -> Text<'static> {
+    icon('\u{EA0F}')
+}
+
+pub fn copy_icon() -> Text<'static> {
+    icon('\u{E92C}')
+}
+
+pub fn rename_icon() -> Text<'static> {
+    icon('\u{E905}')
+}
+
+pub fn crash_icon() -> Text<'static> {
+    icon('\u{EA07}')
+}
+
+/// Greyed out text for placeholders and status messages
+pub fn hint_text<'a>(text: impl ToString) -> Text<'a> {
+    Text::new(text.to_string())
+        .size(14)
+        .style(iced::theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6)))
+}
+
 pub fn icon_button() -> iced::theme::Button {
     iced::theme::Button::Custom(Box::new(ButtonStyle::Icon))
 }
@@ -54,6 +94,10 @@ pub fn log_container(log: &str) -> iced::theme::Container {
     iced::theme::Container::Custom(Box::new(LogContainer::from(log)))
 }
 
+pub fn log_container_for_level(level: Option<Level>) -> iced::theme::Container {
+    iced::theme::Container::Custom(Box::new(LogContainer::from(level)))
+}
+
 /// graphic design is my passion
 pub enum ButtonStyle {
     Icon,
@@ -120,13 +164,23 @@ pub struct LogContainer {
 
 impl From<&str> for LogContainer {
     fn from(log: &str) -> Self {
+        Self::from(if log.starts_with("WARN") {
+            Some(Level::Warn)
+        } else if log.starts_with("ERROR") {
+            Some(Level::Error)
+        } else {
+            None
+        })
+    }
+}
+
+impl From<Option<Level>> for LogContainer {
+    fn from(level: Option<Level>) -> Self {
         Self {
-            background: if log.starts_with("WARN") {
-                Some(Color::new(1., 1., 0.5, 0.5))
-            } else if log.starts_with("ERROR") {
-                Some(Color::new(1., 0.5, 0.5, 0.5))
-            } else {
-                None
+            background: match level {
+                Some(Level::Warn) => Some(Color::new(1., 1., 0.5, 0.5)),
+                Some(Level::Error) => Some(Color::new(1., 0.5, 0.5, 0.5)),
+                _ => None,
             },
         }
     }
@@ -0,0 +1,506 @@
+use crate::instance::{format_timestamp, Instance};
+use crate::plugin_details::format_size;
+use crate::saves;
+use crate::style::{hint_text, text_button};
+use crate::{get_data_dir, Message};
+use anyhow::{Context, Result};
+use fs_extra::dir::CopyOptions;
+use iced::widget::{Button, Column, Row, Scrollable, Space, Text, TextInput};
+use iced::{theme, Alignment, Color, Command, Element, Length};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use time::{format_description, OffsetDateTime};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Something that was deleted, kept in `<data_dir>/trash/<id>` until it expires.
+/// The trashed files and folders are stored as `content/<index of their original path>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    /// Unix timestamp
+    pub deleted: i64,
+    /// What was deleted, e.g. "Instance newest"
+    pub description: String,
+    pub originals: Vec<PathBuf>,
+    /// Size in bytes
+    pub size: u64,
+    /// Deleted instances have to be added to the instance list again when restored
+    pub instance: Option<Box<Instance>>,
+}
+
+fn get_trash_dir() -> Result<PathBuf> {
+    let dir = get_data_dir()
+        .ok_or_else(|| anyhow!("Failed to get app data dir"))?
+        .join("trash");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The combined size of all files below `path`.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+/// Moves a file or folder, falling back to copying when renaming doesn't work, e.g. across file systems.
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if from.is_file() {
+        fs::copy(from, to)
+            .and_then(|_| fs::remove_file(from))
+            .with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    from.to_string_lossy(),
+                    to.to_string_lossy()
+                )
+            })?;
+        return Ok(());
+    }
+    fs::create_dir_all(to)?;
+    let options = CopyOptions {
+        content_only: true,
+        ..CopyOptions::new()
+    };
+    fs_extra::dir::move_dir(from, to, &options).with_context(|| {
+        format!(
+            "Failed to move {} to {}",
+            from.to_string_lossy(),
+            to.to_string_lossy()
+        )
+    })?;
+    Ok(())
+}
+
+/// Moves the given files and folders to the trash as a single entry.
+pub fn put(
+    description: String,
+    originals: Vec<PathBuf>,
+    instance: Option<Box<Instance>>,
+) -> Result<TrashEntry> {
+    let trash_dir = get_trash_dir()?;
+    let now = OffsetDateTime::now_utc();
+    let timestamp = now.format(&format_description::parse(
+        "[year]-[month]-[day] [hour]-[minute]-[second]",
+    )?)?;
+    let mut id = timestamp.clone();
+    let mut n = 1;
+    while trash_dir.join(&id).exists() {
+        n += 1;
+        id = format!("{timestamp} {n}");
+    }
+
+    let entry_dir = trash_dir.join(&id);
+    fs::create_dir_all(entry_dir.join("content"))?;
+    let entry = TrashEntry {
+        id,
+        deleted: now.unix_timestamp(),
+        description,
+        size: originals.iter().map(|p| dir_size(p)).sum(),
+        originals,
+        instance,
+    };
+    let content_dir = entry_dir.join("content");
+    for (index, original) in entry.originals.iter().enumerate() {
+        if let Err(e) = move_dir(original, &content_dir.join(index.to_string())) {
+            put_back(&entry_dir, &entry.originals[..index]);
+            return Err(e);
+        }
+    }
+    // Only list the entry once everything is in it
+    if let Err(e) = File::create(entry_dir.join("entry.json"))
+        .map_err(anyhow::Error::from)
+        .and_then(|f| Ok(serde_json::to_writer_pretty(f, &entry)?))
+    {
+        put_back(&entry_dir, &entry.originals);
+        return Err(e);
+    }
+    info!("Moved {} to the trash", entry.description);
+    Ok(entry)
+}
+
+/// Moves what a failed `put` already moved back where it was, so nothing ends up half in the trash.
+fn put_back(entry_dir: &Path, moved: &[PathBuf]) {
+    let mut stranded = false;
+    for (index, original) in moved.iter().enumerate() {
+        if let Err(e) = move_dir(&entry_dir.join("content").join(index.to_string()), original) {
+            error!(
+                "Failed to move {} back out of the trash: {:#}",
+                original.to_string_lossy(),
+                e
+            );
+            stranded = true;
+        }
+    }
+    // Keep whatever couldn't be moved back
+    if !stranded {
+        fs::remove_dir_all(entry_dir).ok();
+    }
+}
+
+/// All entries in the trash, newest first.
+pub fn list() -> Result<Vec<TrashEntry>> {
+    let mut entries = vec![];
+    for dir in fs::read_dir(get_trash_dir()?)? {
+        let path = dir?.path().join("entry.json");
+        match File::open(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Ok(serde_json::from_reader::<_, TrashEntry>(f)?))
+        {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping {}: {:#}", path.to_string_lossy(), e),
+        }
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted));
+    Ok(entries)
+}
+
+/// Moves the entry's folders back where they were. Fails if something has taken their place since.
+pub fn restore(entry: &TrashEntry) -> Result<()> {
+    if let Some(taken) = entry.originals.iter().find(|p| p.exists()) {
+        bail!("{} already exists", taken.to_string_lossy());
+    }
+    let entry_dir = get_trash_dir()?.join(&entry.id);
+    for (index, original) in entry.originals.iter().enumerate() {
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_dir(&entry_dir.join("content").join(index.to_string()), original)?;
+    }
+    fs::remove_dir_all(&entry_dir)?;
+    info!("Restored {} from the trash", entry.description);
+    Ok(())
+}
+
+pub fn delete(entry: &TrashEntry) -> Result<()> {
+    fs::remove_dir_all(get_trash_dir()?.join(&entry.id))?;
+    info!("Permanently deleted {}", entry.description);
+    Ok(())
+}
+
+/// Permanently deletes entries that have been in the trash for longer than `retention_days`.
+pub fn purge_expired(retention_days: u32) -> Result<()> {
+    let cutoff =
+        OffsetDateTime::now_utc().unix_timestamp() - retention_days as i64 * SECONDS_PER_DAY;
+    for entry in list()?.iter().filter(|e| e.deleted < cutoff) {
+        delete(entry)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashPanel {
+    entries: Option<Result<Vec<TrashEntry>, String>>,
+    retention_days: String,
+    /// The entry that is being restored or deleted
+    working: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TrashMessage {
+    Loaded(Result<Vec<TrashEntry>, String>),
+    Restore(String),
+    Restored(Result<TrashEntry, String>),
+    Delete(String),
+    RetentionChanged(String),
+    Prune,
+}
+
+impl TrashPanel {
+    pub fn new(retention_days: u32) -> (Self, Command<Message>) {
+        (
+            Self {
+                entries: None,
+                retention_days: retention_days.to_string(),
+                working: None,
+            },
+            Command::perform(load(), |entries| {
+                Message::TrashMessage(TrashMessage::Loaded(entries))
+            }),
+        )
+    }
+
+    pub fn retention_days(&self) -> Option<u32> {
+        self.retention_days.trim().parse().ok()
+    }
+
+    pub fn entry(&self, id: &str) -> Option<&TrashEntry> {
+        self.entries
+            .as_ref()?
+            .as_ref()
+            .ok()?
+            .iter()
+            .find(|e| e.id == id)
+    }
+
+    pub fn update(&mut self, message: TrashMessage) -> Command<Message> {
+        match message {
+            TrashMessage::Loaded(entries) => {
+                if let Err(e) = &entries {
+                    error!("Failed to read the trash: {}", e);
+                }
+                self.entries = Some(entries);
+                self.working = None;
+            }
+            // Moving the files back may mean copying them across file systems
+            TrashMessage::Restore(id) => {
+                let Some(entry) = self.entry(&id).cloned().filter(|_| self.working.is_none())
+                else {
+                    return Command::none();
+                };
+                self.working = Some(id);
+                return Command::perform(
+                    async move {
+                        match restore(&entry) {
+                            Ok(()) => Ok(entry),
+                            Err(e) => {
+                                Err(format!("Failed to restore {}: {:#}", entry.description, e))
+                            }
+                        }
+                    },
+                    |result| Message::TrashMessage(TrashMessage::Restored(result)),
+                );
+            }
+            TrashMessage::Restored(result) => {
+                let reload = Command::perform(load(), |entries| {
+                    Message::TrashMessage(TrashMessage::Loaded(entries))
+                });
+                return match result {
+                    Ok(TrashEntry {
+                        instance: Some(instance),
+                        ..
+                    }) => Command::batch([
+                        Command::perform(async {}, move |()| Message::AddInstance(instance)),
+                        reload,
+                    ]),
+                    Ok(_) => reload,
+                    Err(e) => {
+                        error!("{}", e);
+                        self.working = None;
+                        Command::none()
+                    }
+                };
+            }
+            TrashMessage::Delete(id) => {
+                let Some(entry) = self.entry(&id).cloned().filter(|_| self.working.is_none())
+                else {
+                    return Command::none();
+                };
+                self.working = Some(id);
+                return Command::perform(
+                    async move {
+                        if let Err(e) = delete(&entry) {
+                            error!("Failed to delete {}: {:#}", entry.description, e);
+                        }
+                        load().await
+                    },
+                    |entries| Message::TrashMessage(TrashMessage::Loaded(entries)),
+                );
+            }
+            TrashMessage::RetentionChanged(days) => {
+                if days.chars().all(|c| c.is_ascii_digit()) {
+                    self.retention_days = days;
+                }
+            }
+            TrashMessage::Prune => match self.retention_days() {
+                Some(days) => {
+                    return Command::perform(
+                        async move {
+                            purge_expired(days).map_err(|e| format!("{e:#}"))?;
+                            load().await
+                        },
+                        |entries| Message::TrashMessage(TrashMessage::Loaded(entries)),
+                    );
+                }
+                None => error!("Invalid trash retention: '{}'", self.retention_days),
+            },
+        }
+        Command::none()
+    }
+
+    pub fn view(&self) -> Element<TrashMessage> {
+        let days = self.retention_days().map(i64::from);
+        let body: Element<_> = match &self.entries {
+            None => hint_text("Loading...").into(),
+            Some(Err(e)) => hint_text(format!("Failed to read the trash: {e}")).into(),
+            Some(Ok(entries)) if entries.is_empty() => hint_text("The trash is empty").into(),
+            Some(Ok(entries)) => Scrollable::new(entries.iter().fold(
+                Column::new().spacing(10),
+                |column, entry| {
+                    let mut info = format!(
+                        "Deleted {}, {}",
+                        format_timestamp(entry.deleted),
+                        format_size(entry.size)
+                    );
+                    if let Some(days) = days {
+                        let expires = entry.deleted + days * SECONDS_PER_DAY;
+                        let left = (expires - OffsetDateTime::now_utc().unix_timestamp()).max(0)
+                            / SECONDS_PER_DAY;
+                        info.push_str(&format!(", kept for {left} more days"));
+                    }
+                    if self.working.as_ref() == Some(&entry.id) {
+                        info.push_str(", working...");
+                    }
+                    let mut restore_button =
+                        Button::new(Text::new("Restore").size(14)).style(text_button());
+                    let mut delete_button =
+                        Button::new(Text::new("Delete").size(14)).style(theme::Button::Destructive);
+                    if self.working.is_none() {
+                        restore_button =
+                            restore_button.on_press(TrashMessage::Restore(entry.id.clone()));
+                        delete_button =
+                            delete_button.on_press(TrashMessage::Delete(entry.id.clone()));
+                    }
+                    column.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(
+                                Column::new()
+                                    .width(Length::Fill)
+                                    .push(Text::new(&entry.description))
+                                    .push(hint_text(info)),
+                            )
+                            .push(restore_button)
+                            .push(delete_button),
+                    )
+                },
+            ))
+            .height(Length::Fill)
+            .into(),
+        };
+
+        let mut prune_button = Button::new(Text::new("Prune").size(14)).style(text_button());
+        if self.retention_days().is_some() {
+            prune_button = prune_button.on_press(TrashMessage::Prune);
+        }
+        Column::new()
+            .spacing(10)
+            .push(body)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Space::new(Length::Fill, Length::Shrink))
+                    .push(Text::new("Keep deleted items for").size(14))
+                    .push(
+                        TextInput::new("7", &self.retention_days)
+                            .on_input(TrashMessage::RetentionChanged)
+                            .padding(5)
+                            .size(14)
+                            .width(Length::Fixed(50.)),
+                    )
+                    .push(Text::new("days").size(14))
+                    .push(prune_button),
+            )
+            .into()
+    }
+}
+
+async fn load() -> Result<Vec<TrashEntry>, String> {
+    list().map_err(|e| format!("{e:#}"))
+}
+
+/// Asks before an instance is moved to the trash, showing what goes with it.
+#[derive(Debug, Clone)]
+pub struct DeleteConfirmation {
+    pub instance_name: String,
+    path: PathBuf,
+    size: Option<u64>,
+    /// Pilots whose saves live inside the instance
+    pilots: usize,
+    retention_days: u32,
+}
+
+#[derive(Debug, Clone)]
+pub enum DeleteMessage {
+    Sized(u64),
+    Confirm,
+}
+
+impl DeleteConfirmation {
+    pub fn new(instance: &Instance, retention_days: u32) -> (Self, Command<Message>) {
+        let pilots = if instance.isolated_config {
+            instance
+                .config_dir()
+                .and_then(|dir| saves::load_pilots(&dir.join("saves")).ok())
+                .map_or(0, |pilots| pilots.len())
+        } else {
+            0
+        };
+        let path = instance.path.clone();
+        (
+            Self {
+                instance_name: instance.name.clone(),
+                path: path.clone(),
+                size: None,
+                pilots,
+                retention_days,
+            },
+            Command::perform(async move { dir_size(&path) }, |size| {
+                Message::DeleteMessage(DeleteMessage::Sized(size))
+            }),
+        )
+    }
+
+    pub fn update(&mut self, message: DeleteMessage) {
+        if let DeleteMessage::Sized(size) = message {
+            self.size = Some(size);
+        }
+    }
+
+    pub fn view(&self) -> Element<DeleteMessage> {
+        let mut column = Column::new()
+            .spacing(10)
+            .push(Text::new(format!(
+                "Delete the instance {}?",
+                self.instance_name
+            )))
+            .push(hint_text(format!(
+                "Folder: {}",
+                self.path.to_string_lossy()
+            )))
+            .push(hint_text(match self.size {
+                Some(size) => format!("Size: {}", format_size(size)),
+                None => String::from("Size: calculating..."),
+            }));
+        if self.pilots > 0 {
+            column = column.push(
+                Text::new(format!(
+                    "This instance keeps its own saves, {} pilots will be deleted with it",
+                    self.pilots
+                ))
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb(0.9, 0.6, 0.))),
+            );
+        }
+        column
+            .push(hint_text(format!(
+                "It will be kept in the trash for {} days before it's deleted for good",
+                self.retention_days
+            )))
+            .push(
+                Button::new(Text::new("Move to trash").size(14))
+                    .style(theme::Button::Destructive)
+                    .on_press(DeleteMessage::Confirm),
+            )
+            .into()
+    }
+}